@@ -14,6 +14,10 @@ import { useDialog } from "@hooks/useDialog";
 
 function App() {
   const {
+    games,
+    selectedGame,
+    selectGame,
+    status,
     selectedBotId,
     bots,
     leaderboardData,
@@ -37,6 +41,10 @@ function App() {
   return (
     <>
       <AppNavbar
+        games={games.map((g) => g.name)}
+        selectedGame={selectedGame}
+        selectGame={selectGame}
+        status={status}
         loading={loading}
         autoRefresh={autoRefresh}
         setAutoRefresh={setAutoRefresh}
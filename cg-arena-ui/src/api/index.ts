@@ -2,20 +2,60 @@ import {
   BotMinimalResponse,
   CreateBotRequest,
   FetchLeaderboardResponse,
+  GameResponse,
   RenameBotRequest,
+  StatusResponse,
 } from "@models";
 
 const host = import.meta.env.DEV ? "http://127.0.0.1:1234" : "";
 
+// selected game is kept in the url, default game is used if it is not set
+export const selectedGame = new URLSearchParams(window.location.search).get("game");
+const api = selectedGame
+  ? `${host}/api/games/${encodeURIComponent(selectedGame)}`
+  : `${host}/api`;
+
+export const fetchGames = async (): Promise<GameResponse[]> => {
+  const response = await fetch(`${host}/api/games`);
+  return await parseResponse<GameResponse[]>(response);
+};
+
+// queue_stats is left out, it changes too often to refetch on it
+const EVENT_TYPES = [
+  "bot_created",
+  "bot_renamed",
+  "bot_archived",
+  "bot_paused",
+  "bot_deleted",
+  "build_changed",
+  "match_finished",
+  "lagged",
+];
+
+export const subscribeEvents = (
+  onEvent: (type: string) => void
+): EventSource => {
+  const source = new EventSource(`${api}/events`);
+  for (const type of EVENT_TYPES) {
+    source.addEventListener(type, () => onEvent(type));
+  }
+  return source;
+};
+
+export const fetchStatus = async (): Promise<StatusResponse> => {
+  const response = await fetch(`${api}/status`);
+  return await parseResponse<StatusResponse>(response);
+};
+
 export const fetchBots = async (): Promise<BotMinimalResponse[]> => {
-  const response = await fetch(`${host}/api/bots`);
+  const response = await fetch(`${api}/bots`);
   return await parseResponse<BotMinimalResponse[]>(response);
 };
 
 export const fetchLeaderboard = async (
   id: string
 ): Promise<FetchLeaderboardResponse | undefined> => {
-  const response = await fetch(`${host}/api/bots/${id}`);
+  const response = await fetch(`${api}/bots/${id}`);
   if (response.status == 404) return undefined;
   return await parseResponse<FetchLeaderboardResponse>(response);
 };
@@ -23,7 +63,7 @@ export const fetchLeaderboard = async (
 export const submitNewBot = async (
   payload: CreateBotRequest
 ): Promise<BotMinimalResponse> => {
-  const req = new Request(`${host}/api/bots`, {
+  const req = new Request(`${api}/bots`, {
     method: "POST",
     body: JSON.stringify(payload),
     headers: {
@@ -39,7 +79,7 @@ export const renameBot = async (
   id: string,
   payload: RenameBotRequest
 ): Promise<BotMinimalResponse> => {
-  const req = new Request(`${host}/api/bots/${id}`, {
+  const req = new Request(`${api}/bots/${id}`, {
     method: "PATCH",
     body: JSON.stringify(payload),
     headers: {
@@ -52,7 +92,7 @@ export const renameBot = async (
 };
 
 export const deleteBot = async (id: string) => {
-  const req = new Request(`${host}/api/bots/${id}`, {
+  const req = new Request(`${api}/bots/${id}`, {
       method: "DELETE"
   });
   const response = await fetch(req);
@@ -8,8 +8,13 @@ import {
   Stack,
 } from "react-bootstrap";
 import ThemeSwitcher from "./ThemeSwitcher";
+import { StatusResponse } from "@models";
 
 interface AppNavbarProps {
+  games: string[];
+  selectedGame?: string;
+  selectGame: (game: string) => void;
+  status?: StatusResponse;
   loading: boolean;
   openSubmitDialog: () => void;
   autoRefresh: boolean,
@@ -17,6 +22,10 @@ interface AppNavbarProps {
 }
 
 const AppNavbar: React.FC<AppNavbarProps> = ({
+  games,
+  selectedGame,
+  selectGame,
+  status,
   loading,
   openSubmitDialog,
   autoRefresh,
@@ -26,9 +35,29 @@ const AppNavbar: React.FC<AppNavbarProps> = ({
     <Navbar className="bg-body-tertiary">
       <Container>
         <Navbar.Brand href="#home">CG Arena</Navbar.Brand>
+        {status && (
+          <Navbar.Text className="small">
+            v{status.version} · {status.bots} bots · {status.matches} matches ·{" "}
+            {status.matches_per_minute_5m.toFixed(1)} matches/min · queue{" "}
+            {status.queue_length}
+            {status.matchmaking_paused && " · matchmaking paused"}
+          </Navbar.Text>
+        )}
 
         <Stack direction="horizontal" gap={3}>
           {loading && <Spinner animation="border" />}
+          {games.length > 1 && (
+            <Form.Select
+              value={selectedGame}
+              onChange={(e) => selectGame(e.target.value)}
+            >
+              {games.map((g) => (
+                <option key={g} value={g}>
+                  {g}
+                </option>
+              ))}
+            </Form.Select>
+          )}
           <Form.Switch
             checked={autoRefresh}
             onChange={(e) => setAutoRefresh(e.target.checked)}
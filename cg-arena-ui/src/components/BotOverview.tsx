@@ -1,7 +1,9 @@
 import {
   BuildResponse,
   LeaderboardBotOverviewResponse,
+  MetricResponse,
   rating_score,
+  SeatResponse,
 } from "@models";
 import React from "react";
 import { Badge, Button, Stack, Table } from "react-bootstrap";
@@ -30,17 +32,45 @@ const BotOverview: React.FC<BotOverviewProps> = ({
           <th>Rating</th>
           <th>Matches played</th>
           <th>Matches with error</th>
+          <th>Win rate by seat</th>
+          <th>Avg score (margin)</th>
+          <th>Metrics (mean, p10/p50/p90)</th>
           <th>Build</th>
           <th>Actions</th>
         </tr>
       </thead>
       <tbody>
         <tr key={bot.id}>
-          <td>{bot.name}</td>
+          <td>
+            {bot.name}{" "}
+            {bot.paused_reason && (
+              <Badge bg="warning" title={`Paused: ${bot.paused_reason}`}>
+                paused
+              </Badge>
+            )}
+          </td>
           <td>{bot.language}</td>
           <td>{rating_score(bot)}</td>
           <td>{bot.matches_played}</td>
-          <td>{bot.matches_with_error}</td>
+          <td>
+            <Stack>
+              <span>{bot.matches_with_error}</span>
+              {Object.entries(bot.errors_by_kind).map(([kind, count]) => (
+                <small key={kind}>{`${kind}: ${count}`}</small>
+              ))}
+            </Stack>
+          </td>
+          <td>
+            <Seats seats={bot.seats} />
+          </td>
+          <td>
+            {bot.average_score != null
+              ? `${bot.average_score.toFixed(1)} (${(bot.average_score_margin ?? 0).toFixed(1)})`
+              : "-"}
+          </td>
+          <td>
+            <Metrics metrics={bot.metrics} />
+          </td>
           <td>
             <Builds builds={bot.builds} showContentDialog={showContentDialog} />
           </td>
@@ -60,6 +90,35 @@ const BotOverview: React.FC<BotOverviewProps> = ({
   );
 };
 
+const Seats: React.FC<{ seats: SeatResponse[] }> = ({ seats }) => {
+  const played = seats.filter((seat) => seat.win_rate != null);
+  if (played.length == 0) return <span>-</span>;
+  return (
+    <Stack>
+      {played.map((seat) => (
+        <span key={seat.index}>
+          {`P${seat.index + 1}: ${((seat.win_rate ?? 0) * 100).toFixed(1)}%`}
+          {` (${seat.wins}/${seat.draws}/${seat.loses})`}
+        </span>
+      ))}
+    </Stack>
+  );
+};
+
+const Metrics: React.FC<{ metrics: MetricResponse[] }> = ({ metrics }) => {
+  if (metrics.length == 0) return <span>-</span>;
+  return (
+    <Stack>
+      {metrics.map((metric) => (
+        <span key={metric.name}>
+          {`${metric.name}: ${metric.mean.toFixed(2)}`}
+          {` (${metric.p10}/${metric.p50}/${metric.p90})`}
+        </span>
+      ))}
+    </Stack>
+  );
+};
+
 interface BuildsProps {
   builds: BuildResponse[];
   showContentDialog: (data: { title: string; content: string }) => void;
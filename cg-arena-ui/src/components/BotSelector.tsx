@@ -23,6 +23,7 @@ const BotSelector: React.FC<BotSelectorProps> = ({
           {items.map((item) => (
             <option value={item.id} key={item.id}>
               {item.name}
+              {item.author && ` (${item.author})`}
             </option>
           ))}
         </Form.Select>
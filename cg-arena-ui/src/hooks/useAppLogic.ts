@@ -2,7 +2,9 @@ import {
   BotMinimalResponse,
   CreateBotRequest,
   FetchLeaderboardResponse,
+  GameResponse,
   RenameBotRequest,
+  StatusResponse,
 } from "@models";
 import { useCallback, useEffect, useState } from "react";
 import * as api from "@api";
@@ -15,6 +17,9 @@ export const useAppLogic = () => {
     FetchLeaderboardResponse | undefined
   >();
   const [autoRefresh, setAutoRefresh] = useState(true);
+  const [stale, setStale] = useState(false);
+  const [games, setGames] = useState<GameResponse[]>([]);
+  const [status, setStatus] = useState<StatusResponse | undefined>();
 
   const fetchInitialBots = useCallback(async () => {
     setLoading(true);
@@ -47,13 +52,30 @@ export const useAppLogic = () => {
 
   // effects
 
-  // auto refresh
+  // live updates
   useEffect(() => {
     if (!autoRefresh) return;
 
-    const interval = setInterval(refreshLeaderboard, 3000); // in ms
-    return () => clearInterval(interval);
-  }, [refreshLeaderboard, autoRefresh]);
+    const events = api.subscribeEvents((type) => {
+      if (type.startsWith("bot_") || type == "lagged") {
+        api.fetchBots().then(setBots);
+      }
+      setStale(true);
+    });
+    return () => events.close();
+  }, [autoRefresh, setBots]);
+
+  // leaderboard is refetched at most once per interval
+  useEffect(() => {
+    if (!stale) return;
+
+    const timeout = setTimeout(() => {
+      setStale(false);
+      refreshLeaderboard();
+      api.fetchStatus().then(setStatus);
+    }, 3000); // in ms
+    return () => clearTimeout(timeout);
+  }, [stale, refreshLeaderboard]);
 
   // select bot from the list
   useEffect(() => {
@@ -67,6 +89,16 @@ export const useAppLogic = () => {
     fetchInitialBots();
   }, [fetchInitialBots]);
 
+  // load games initially
+  useEffect(() => {
+    api.fetchGames().then(setGames);
+  }, [setGames]);
+
+  // load status initially
+  useEffect(() => {
+    api.fetchStatus().then(setStatus);
+  }, [setStatus]);
+
   // handle selection change
   useEffect(() => {
     setLeaderboardData(undefined);
@@ -84,6 +116,12 @@ export const useAppLogic = () => {
 
   // exported functions
 
+  const selectGame = useCallback((game: string) => {
+    const url = new URL(window.location.href);
+    url.searchParams.set("game", game);
+    window.location.assign(url);
+  }, []);
+
   const submitNewBot = useCallback(
     async (req: CreateBotRequest) => {
       const bot = await api.submitNewBot(req);
@@ -120,6 +158,10 @@ export const useAppLogic = () => {
   );
 
   return {
+    games,
+    selectedGame: api.selectedGame ?? games[0]?.name,
+    selectGame,
+    status,
     selectedBotId,
     bots,
     leaderboardData,
@@ -8,9 +8,30 @@ export interface RenameBotRequest {
   name: string;
 }
 
+export interface GameResponse {
+  name: string;
+}
+
+export interface StatusResponse {
+  version: string;
+  uptime_secs: number;
+  bots: number;
+  archived_bots: number;
+  paused_bots: number;
+  matches: number;
+  builds: { pending: number; running: number; failed: number };
+  workers: { name: string; threads: number; matches_in_progress: number }[];
+  queue_length: number;
+  matchmaking_paused: boolean;
+  sprt_running: boolean;
+  matches_per_minute_5m: number;
+  matches_per_minute_60m: number;
+}
+
 export interface BotMinimalResponse {
   id: string;
   name: string;
+  author?: string;
 }
 
 export interface FetchLeaderboardResponse {
@@ -26,7 +47,32 @@ export interface LeaderboardBotOverviewResponse {
   rating_sigma: number;
   matches_played: number;
   matches_with_error: number;
+  errors_by_kind: Record<string, number>;
+  paused_reason?: string;
   builds: BuildResponse[];
+  seats: SeatResponse[];
+  average_score?: number;
+  average_score_margin?: number;
+  metrics: MetricResponse[];
+}
+
+export interface MetricResponse {
+  name: string;
+  count: number;
+  mean: number;
+  min: number;
+  max: number;
+  p10: number;
+  p50: number;
+  p90: number;
+}
+
+export interface SeatResponse {
+  index: number;
+  wins: number;
+  draws: number;
+  loses: number;
+  win_rate?: number;
 }
 
 export interface LeaderboardItemResponse {
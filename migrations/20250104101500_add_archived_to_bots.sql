@@ -0,0 +1 @@
+ALTER TABLE bots ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE matches ADD COLUMN group_id INTEGER;
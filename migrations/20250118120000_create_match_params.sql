@@ -0,0 +1,8 @@
+CREATE TABLE match_params
+(
+    match_id INTEGER NOT NULL,
+    name     TEXT    NOT NULL,
+    value    TEXT    NOT NULL,
+    PRIMARY KEY (match_id, name),
+    FOREIGN KEY (match_id) REFERENCES matches (id) ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+ALTER TABLE participations ADD COLUMN team INTEGER;
@@ -0,0 +1,10 @@
+CREATE TABLE participation_metrics
+(
+    match_id INTEGER NOT NULL,
+    bot_id   INTEGER NOT NULL,
+    name     TEXT    NOT NULL,
+    value    REAL    NOT NULL,
+    PRIMARY KEY (match_id, bot_id, name),
+    FOREIGN KEY (match_id) REFERENCES matches (id) ON DELETE CASCADE,
+    FOREIGN KEY (bot_id) REFERENCES bots (id) ON DELETE CASCADE
+);
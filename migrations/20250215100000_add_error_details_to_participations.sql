@@ -0,0 +1,2 @@
+ALTER TABLE participations ADD COLUMN error_kind TEXT;
+ALTER TABLE participations ADD COLUMN error_message TEXT;
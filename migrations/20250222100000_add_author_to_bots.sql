@@ -0,0 +1 @@
+ALTER TABLE bots ADD COLUMN author TEXT;
@@ -0,0 +1,10 @@
+ALTER TABLE matches ADD COLUMN created_at INTEGER;
+-- the play time of existing matches is unknown, the latest submission of their bots is the closest
+-- known time they could have been played at
+UPDATE matches
+SET created_at = COALESCE(
+        (SELECT MAX(CAST(strftime('%s', bots.created_at) AS INTEGER))
+         FROM participations
+                  JOIN bots ON bots.id = participations.bot_id
+         WHERE participations.match_id = matches.id),
+        CAST(strftime('%s', 'now') AS INTEGER));
@@ -0,0 +1,6 @@
+CREATE TABLE computed_stats
+(
+    id            INTEGER PRIMARY KEY CHECK (id = 1),
+    last_match_id INTEGER NOT NULL,
+    data          TEXT    NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE computed_stats ADD COLUMN rating_settings TEXT;
+ALTER TABLE computed_stats ADD COLUMN has_pruned INTEGER NOT NULL DEFAULT 1;
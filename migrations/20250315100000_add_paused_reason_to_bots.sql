@@ -0,0 +1,2 @@
+-- bots excluded from matchmaking by the arena itself, e.g. for failing too often, unlike archived by the user
+ALTER TABLE bots ADD COLUMN paused_reason TEXT;
@@ -0,0 +1,198 @@
+use crate::api::errors::ApiError;
+use crate::config::{Role, UserConfig};
+use axum::extract::{Extension, Request, State};
+use axum::http::{header, HeaderMap, Method};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use std::sync::Arc;
+
+/// header with the user token for the CLI and scripts
+pub const API_TOKEN_HEADER: &str = "x-api-token";
+
+/// User who made the request, available to the handlers as an extension when auth is enabled.
+#[derive(Clone)]
+pub struct AuthUser {
+    pub name: String,
+    pub role: Role,
+}
+
+impl AuthUser {
+    /// admins manage every bot, other users only their own ones
+    pub fn can_manage(&self, author: Option<&str>) -> bool {
+        self.role == Role::Admin || author == Some(self.name.as_str())
+    }
+}
+
+/// Checks the credentials of every request when at least one user is configured.
+/// Browsers authenticate with HTTP Basic auth (user name and token as password),
+/// other clients send the token in `X-Api-Token` header.
+/// Anything but reading requires at least [`Role::Member`].
+pub async fn authenticate(
+    State(users): State<Arc<Vec<UserConfig>>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    // CORS preflight requests never carry credentials
+    if users.is_empty() || request.method() == Method::OPTIONS {
+        return next.run(request).await;
+    }
+
+    let Some(user) = find_user(&users, request.headers()) else {
+        return (
+            [(header::WWW_AUTHENTICATE, "Basic realm=\"CG Arena\"")],
+            ApiError::Unauthorized,
+        )
+            .into_response();
+    };
+
+    let read = matches!(*request.method(), Method::GET | Method::HEAD);
+    if !read && user.role < Role::Member {
+        return ApiError::Forbidden.into_response();
+    }
+
+    request.extensions_mut().insert(AuthUser {
+        name: user.name.clone(),
+        role: user.role,
+    });
+    next.run(request).await
+}
+
+/// Limits anything but reading to [`Role::Admin`], layered on the routes after [`authenticate`].
+pub async fn require_admin(
+    user: Option<Extension<AuthUser>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let read = matches!(*request.method(), Method::GET | Method::HEAD);
+    match user {
+        Some(Extension(user)) if !read && user.role < Role::Admin => {
+            ApiError::Forbidden.into_response()
+        }
+        _ => next.run(request).await,
+    }
+}
+
+fn find_user<'a>(users: &'a [UserConfig], headers: &HeaderMap) -> Option<&'a UserConfig> {
+    if let Some(token) = headers.get(API_TOKEN_HEADER) {
+        let token = token.to_str().ok()?;
+        return users.iter().find(|u| constant_time_eq(&u.token, token));
+    }
+
+    let authorization = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let encoded = authorization.strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(BASE64_STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (name, password) = decoded.split_once(':')?;
+    users
+        .iter()
+        .find(|u| u.name == name && constant_time_eq(&u.token, password))
+}
+
+/// comparison time does not depend on the position of the first mismatch
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use axum::Router;
+
+    async fn start_server() -> String {
+        let users = vec![
+            UserConfig {
+                name: "alice".to_string(),
+                token: "admin-secret".to_string(),
+                role: Role::Admin,
+            },
+            UserConfig {
+                name: "bob".to_string(),
+                token: "read-secret".to_string(),
+                role: Role::ReadOnly,
+            },
+            UserConfig {
+                name: "carol".to_string(),
+                token: "member-secret".to_string(),
+                role: Role::Member,
+            },
+        ];
+        let admin_router = Router::new()
+            .route(
+                "/sprt",
+                get(|| async { "sprt" }).post(|| async { "started" }),
+            )
+            .route_layer(axum::middleware::from_fn(require_admin));
+        let router = Router::new()
+            .route(
+                "/bots",
+                get(|| async { "list" }).delete(|| async { "deleted" }),
+            )
+            .merge(admin_router)
+            .layer(axum::middleware::from_fn_with_state(
+                Arc::new(users),
+                authenticate,
+            ));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        url
+    }
+
+    #[tokio::test]
+    async fn roles_are_enforced() {
+        let base = start_server().await;
+        let (url, sprt_url) = (format!("{base}/bots"), format!("{base}/sprt"));
+        let http = reqwest::Client::new();
+        let status = |request: reqwest::RequestBuilder| async move {
+            request.send().await.unwrap().status()
+        };
+
+        assert_eq!(status(http.get(&url)).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            status(http.get(&url).basic_auth("alice", Some("read-secret"))).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(http.get(&url).basic_auth("bob", Some("read-secret"))).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(http.delete(&url).basic_auth("bob", Some("read-secret"))).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status(http.delete(&url).header(API_TOKEN_HEADER, "member-secret")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(
+                http.get(&sprt_url)
+                    .header(API_TOKEN_HEADER, "member-secret")
+            )
+            .await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(
+                http.post(&sprt_url)
+                    .header(API_TOKEN_HEADER, "member-secret")
+            )
+            .await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status(
+                http.post(&sprt_url)
+                    .header(API_TOKEN_HEADER, "admin-secret")
+            )
+            .await,
+            StatusCode::OK
+        );
+    }
+}
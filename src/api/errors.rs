@@ -8,12 +8,21 @@ pub enum ApiError {
     #[error("Not found")]
     NotFound,
 
+    #[error("Authentication required")]
+    Unauthorized,
+
+    #[error("Not allowed for this user")]
+    Forbidden,
+
     #[error("Validation failed: {0}")]
     ValidationFailed(anyhow::Error),
 
     #[error("Conflict: {0}")]
     Conflict(anyhow::Error),
 
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(anyhow::Error),
+
     #[error(transparent)]
     Internal(#[from] anyhow::Error),
 }
@@ -22,8 +31,11 @@ impl ApiError {
     fn get_status_code(&self) -> StatusCode {
         match self {
             ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden => StatusCode::FORBIDDEN,
             ApiError::ValidationFailed(_) => StatusCode::BAD_REQUEST,
             ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::QuotaExceeded(_) => StatusCode::FORBIDDEN,
             ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -31,8 +43,11 @@ impl ApiError {
     fn get_error_code(&self) -> &'static str {
         match self {
             ApiError::NotFound => "not_found",
+            ApiError::Unauthorized => "unauthorized",
+            ApiError::Forbidden => "forbidden",
             ApiError::ValidationFailed(_) => "validation_failed",
             ApiError::Conflict(_) => "already_exists",
+            ApiError::QuotaExceeded(_) => "quota_exceeded",
             ApiError::Internal(_) => "internal_error",
         }
     }
@@ -1,10 +1,16 @@
+pub mod auth;
 mod errors;
 mod routes;
 mod web_router;
 
 use crate::api::web_router::create_web_router;
 use crate::arena::ArenaCommand;
+use crate::config::UserConfig;
+use crate::db::Database;
+use crate::metrics::GameMetrics;
 use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio::sync::mpsc::Sender;
 use tokio_util::sync::CancellationToken;
@@ -12,35 +18,111 @@ use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 use tracing::{error, info};
 
+/// Hosted game with its arena.
+pub struct GameHandle {
+    pub name: String,
+    pub arena_tx: Sender<ArenaCommand>,
+    /// read-only queries bypass the arena
+    pub db: Database,
+    pub metrics: Arc<GameMetrics>,
+}
+
+/// the first of `games` is the default one, HTTPS is served if `tls` is set
 pub async fn start(
     listener: TcpListener,
-    arena_tx: Sender<ArenaCommand>,
+    games: Vec<GameHandle>,
+    threads: usize,
+    users: Vec<UserConfig>,
+    tls: Option<RustlsConfig>,
     cancellation_token: CancellationToken,
 ) {
-    let app_state = AppState { arena_tx };
-    let router = create_router(app_state).await;
-    let server = axum::serve(listener, router)
-        .with_graceful_shutdown(async move { cancellation_token.cancelled().await });
+    let router = create_router(games, threads, users).await;
 
     info!("Arena API server started");
-    if let Err(e) = server.await {
+    let res = match tls {
+        Some(tls) => serve_tls(listener, router, tls, cancellation_token).await,
+        None => {
+            axum::serve(listener, router)
+                .with_graceful_shutdown(async move { cancellation_token.cancelled().await })
+                .await
+        }
+    };
+    if let Err(e) = res {
         error!("API Server error: {}", e);
     }
     info!("Arena API server closed");
 }
 
-async fn create_router(app_state: AppState) -> Router {
-    let api_router = Router::new()
-        .merge(routes::bots::create_router())
-        .with_state(app_state);
+async fn serve_tls(
+    listener: TcpListener,
+    router: Router,
+    tls: RustlsConfig,
+    cancellation_token: CancellationToken,
+) -> std::io::Result<()> {
+    let handle = axum_server::Handle::new();
+    let shutdown_handle = handle.clone();
+    tokio::spawn(async move {
+        cancellation_token.cancelled().await;
+        shutdown_handle.graceful_shutdown(None);
+    });
+
+    axum_server::from_tcp_rustls(listener.into_std()?, tls)
+        .handle(handle)
+        .serve(router.into_make_service())
+        .await
+}
+
+async fn create_router(games: Vec<GameHandle>, threads: usize, users: Vec<UserConfig>) -> Router {
+    let names = games.iter().map(|g| g.name.clone()).collect();
+    let mut api_router = Router::new().merge(routes::games::create_router(names));
+    let metrics_router = routes::metrics::create_router(
+        threads,
+        games
+            .iter()
+            .map(|g| (g.name.clone(), g.arena_tx.clone(), Arc::clone(&g.metrics)))
+            .collect(),
+    );
+
+    for (
+        index,
+        GameHandle {
+            name, arena_tx, db, ..
+        },
+    ) in games.into_iter().enumerate()
+    {
+        let game_router = create_game_router(AppState { arena_tx, db });
+        // routes without game prefix are served by the default game
+        if index == 0 {
+            api_router = api_router.merge(game_router.clone());
+        }
+        api_router = api_router.nest(&format!("/games/{name}"), game_router);
+    }
 
     create_web_router()
         .nest("/api", api_router)
+        .merge(metrics_router)
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::new(users),
+            auth::authenticate,
+        ))
         .layer(CorsLayer::permissive())
         .layer(TraceLayer::new_for_http())
 }
 
+fn create_game_router(app_state: AppState) -> Router {
+    Router::new()
+        .merge(routes::admin::create_router())
+        .merge(routes::bots::create_router())
+        .merge(routes::events::create_router())
+        .merge(routes::matches::create_router())
+        .merge(routes::matrix::create_router())
+        .merge(routes::sprt::create_router())
+        .merge(routes::status::create_router())
+        .with_state(app_state)
+}
+
 #[derive(Clone)]
 pub(crate) struct AppState {
     pub arena_tx: Sender<ArenaCommand>,
+    pub db: Database,
 }
@@ -0,0 +1,33 @@
+use crate::api::auth;
+use crate::api::errors::ApiError;
+use crate::api::AppState;
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::Serialize;
+use tracing::warn;
+
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/backup", post(backup))
+        .route_layer(axum::middleware::from_fn(auth::require_admin))
+}
+
+#[derive(Serialize)]
+struct BackupResponse {
+    pub path: String,
+}
+
+/// Runs on a pool connection, so the arena keeps playing and recording matches meanwhile.
+async fn backup(State(app_state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
+    let path = app_state
+        .db
+        .backup()
+        .await
+        .inspect_err(|e| warn!("Backup failed: {e:#}"))?;
+
+    Ok(Json(BackupResponse {
+        path: path.display().to_string(),
+    }))
+}
@@ -1,26 +1,31 @@
+use crate::api::auth::AuthUser;
 use crate::api::errors::ApiError;
 use crate::api::AppState;
 use crate::arena::{
-    ArenaCommand, BotMinimal, CreateBotCommand, CreateBotResult, DeleteBotCommand,
-    FetchBotsCommand, FetchLeaderboardCommand, FetchLeaderboardResult, LeaderboardBotOverview,
-    LeaderboardItem, RenameBotCommand, RenameBotResult,
+    ArchiveBotCommand, ArchiveBotResult, ArenaCommand, BotMinimal, CreateBotCommand,
+    CreateBotResult, DeleteBotCommand, FetchBotsCommand, FetchLeaderboardCommand,
+    FetchLeaderboardResult, LeaderboardBotOverview, LeaderboardItem, MetricSummary,
+    RenameBotCommand, RenameBotResult,
 };
 use crate::domain::{BotId, BotName, Build, BuildResult, BuildStatus, Language, SourceCode};
 use anyhow::anyhow;
-use axum::extract::Path;
+use axum::extract::{Path, Query};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::routing::patch;
 use axum::{
-    extract::State,
+    extract::{Extension, State},
     routing::{delete, get, post},
     Json, Router,
 };
 use chrono::{DateTime, Local};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use tokio::sync::oneshot;
 
+const AUTHOR_LEN_LIMIT: usize = 64;
+
 pub fn create_router() -> Router<AppState> {
     Router::new()
         .route("/bots", post(create_bot))
@@ -28,6 +33,7 @@ pub fn create_router() -> Router<AppState> {
         .route("/bots/:id", delete(delete_bot))
         .route("/bots/:id", get(fetch_bot_leaderboard))
         .route("/bots/:id", patch(rename_bot))
+        .route("/bots/:id/archive", post(archive_bot))
 }
 
 #[derive(Deserialize)]
@@ -35,6 +41,14 @@ struct CreateBotRequest {
     pub name: String,
     pub source_code: String,
     pub language: String,
+    /// only used when authentication is disabled, otherwise the authenticated user is the author
+    #[serde(default)]
+    pub author: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FetchBotsQuery {
+    pub author: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -42,10 +56,18 @@ struct RenameBotRequest {
     pub name: String,
 }
 
+#[derive(Deserialize)]
+struct ArchiveBotRequest {
+    pub archived: bool,
+}
+
 #[derive(Serialize)]
-struct BotMinimalResponse {
+pub struct BotMinimalResponse {
     pub id: i64,
     pub name: String,
+    pub archived: bool,
+    pub paused_reason: Option<String>,
+    pub author: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -64,10 +86,55 @@ struct LeaderboardBotOverviewResponse {
     pub matches_played: usize,
     pub matches_with_error: usize,
     pub builds: Vec<BuildResponse>,
+    pub archived: bool,
+    /// why the arena excluded the bot from matchmaking, unarchiving resumes it
+    pub paused_reason: Option<String>,
+    pub seats: Vec<SeatResponse>,
+    /// unclassified errors are counted under "unclassified"
+    pub errors_by_kind: BTreeMap<&'static str, usize>,
+    pub average_score: Option<f64>,
+    pub average_score_margin: Option<f64>,
+    pub metrics: Vec<MetricResponse>,
+}
+
+#[derive(Serialize)]
+struct SeatResponse {
+    pub index: usize,
+    pub wins: usize,
+    pub draws: usize,
+    pub loses: usize,
+    pub win_rate: Option<f64>,
 }
 
 #[derive(Serialize)]
-struct BuildResponse {
+pub struct MetricResponse {
+    pub name: String,
+    pub count: usize,
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p10: f64,
+    pub p50: f64,
+    pub p90: f64,
+}
+
+impl From<MetricSummary> for MetricResponse {
+    fn from(m: MetricSummary) -> Self {
+        MetricResponse {
+            name: m.name,
+            count: m.count,
+            mean: m.mean,
+            min: m.min,
+            max: m.max,
+            p10: m.p10,
+            p50: m.p50,
+            p90: m.p90,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct BuildResponse {
     pub worker_name: String,
     pub status: String,
     pub stderr: Option<String>,
@@ -84,6 +151,7 @@ struct LeaderboardItemResponse {
     pub loses: usize,
     pub draws: usize,
     pub created_at: String,
+    pub archived: bool,
 }
 
 impl From<BotMinimal> for BotMinimalResponse {
@@ -91,6 +159,9 @@ impl From<BotMinimal> for BotMinimalResponse {
         BotMinimalResponse {
             id: value.id.into(),
             name: value.name.into(),
+            archived: value.archived,
+            paused_reason: value.paused_reason,
+            author: value.author,
         }
     }
 }
@@ -109,6 +180,7 @@ impl From<LeaderboardItem> for LeaderboardItemResponse {
             created_at: DateTime::<Local>::from(item.created_at)
                 .format("%d/%m/%Y %H:%M")
                 .to_string(),
+            archived: item.archived,
         }
     }
 }
@@ -124,6 +196,28 @@ impl From<LeaderboardBotOverview> for LeaderboardBotOverviewResponse {
             matches_played: v.matches_played,
             matches_with_error: v.matches_with_error,
             builds: v.builds.into_iter().map(|b| b.into()).collect(),
+            archived: v.archived,
+            paused_reason: v.paused_reason,
+            seats: v
+                .seats
+                .into_iter()
+                .enumerate()
+                .map(|(index, wdl)| SeatResponse {
+                    index,
+                    wins: wdl.wins,
+                    draws: wdl.draws,
+                    loses: wdl.loses,
+                    win_rate: wdl.win_rate(),
+                })
+                .collect(),
+            errors_by_kind: v
+                .errors_by_kind
+                .into_iter()
+                .map(|(kind, cnt)| (kind.map_or("unclassified", |k| k.as_str()), cnt))
+                .collect(),
+            average_score: v.average_score,
+            average_score_margin: v.average_score_margin,
+            metrics: v.metrics.into_iter().map(Into::into).collect(),
         }
     }
 }
@@ -157,6 +251,7 @@ impl From<FetchLeaderboardResult> for FetchLeaderboardResponse {
 
 async fn create_bot(
     State(app_state): State<AppState>,
+    user: Option<Extension<AuthUser>>,
     Json(payload): Json<CreateBotRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     let name: BotName = payload
@@ -171,6 +266,27 @@ async fn create_bot(
         .language
         .try_into()
         .map_err(ApiError::ValidationFailed)?;
+    let author = payload.author.map(|a| a.trim().to_string());
+    // authenticated users can't submit on behalf of somebody else
+    let author = match user {
+        Some(Extension(user)) => {
+            if author.is_some_and(|a| a != user.name) {
+                return Err(ApiError::ValidationFailed(anyhow!(
+                    "Author should match the authenticated user"
+                )));
+            }
+            Some(user.name)
+        }
+        None => author,
+    };
+    if author
+        .as_ref()
+        .is_some_and(|a| a.is_empty() || a.len() > AUTHOR_LEN_LIMIT)
+    {
+        return Err(ApiError::ValidationFailed(anyhow!(
+            "Author should be 1 to {AUTHOR_LEN_LIMIT} characters long"
+        )));
+    }
 
     let (tx, rx) = oneshot::channel();
 
@@ -178,6 +294,7 @@ async fn create_bot(
         name,
         source_code,
         language,
+        author,
         response: tx,
     };
 
@@ -194,15 +311,38 @@ async fn create_bot(
         CreateBotResult::DuplicateName => Err(ApiError::Conflict(anyhow!(
             "Bot with the same name already exists"
         ))),
+        CreateBotResult::QuotaExceeded => Err(ApiError::QuotaExceeded(anyhow!(
+            "Author already has the maximum amount of active bots"
+        ))),
     }
 }
 
+/// passes when authentication is disabled, the user is an admin or the author of the bot
+async fn check_owner(
+    app_state: &AppState,
+    user: Option<Extension<AuthUser>>,
+    id: BotId,
+) -> Result<(), ApiError> {
+    let Some(Extension(user)) = user else {
+        return Ok(());
+    };
+    let Some(author) = app_state.db.fetch_bot_author(id).await? else {
+        return Err(ApiError::NotFound);
+    };
+    if !user.can_manage(author.as_deref()) {
+        return Err(ApiError::Forbidden);
+    }
+    Ok(())
+}
+
 async fn rename_bot(
     State(app_state): State<AppState>,
+    user: Option<Extension<AuthUser>>,
     Path(id): Path<i64>,
     Json(payload): Json<RenameBotRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     let id: BotId = id.into();
+    check_owner(&app_state, user, id).await?;
     let new_name: BotName = payload
         .name
         .try_into()
@@ -232,11 +372,46 @@ async fn rename_bot(
     }
 }
 
+async fn archive_bot(
+    State(app_state): State<AppState>,
+    user: Option<Extension<AuthUser>>,
+    Path(id): Path<i64>,
+    Json(payload): Json<ArchiveBotRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let id: BotId = id.into();
+    check_owner(&app_state, user, id).await?;
+
+    let (tx, rx) = oneshot::channel();
+    let command = ArchiveBotCommand {
+        id,
+        archived: payload.archived,
+        response: tx,
+    };
+
+    app_state
+        .arena_tx
+        .send(ArenaCommand::ArchiveBot(command))
+        .await
+        .map_err(|e| anyhow!(e))?;
+
+    let res = rx.await.map_err(|e| anyhow!(e))?;
+
+    match res {
+        ArchiveBotResult::Updated(bot_minimal) => Ok(Json(BotMinimalResponse::from(bot_minimal))),
+        ArchiveBotResult::NotFound => Err(ApiError::NotFound),
+        ArchiveBotResult::QuotaExceeded => Err(ApiError::QuotaExceeded(anyhow!(
+            "Author already has the maximum amount of active bots"
+        ))),
+    }
+}
+
 async fn delete_bot(
     State(app_state): State<AppState>,
+    user: Option<Extension<AuthUser>>,
     Path(id): Path<i64>,
 ) -> Result<impl IntoResponse, ApiError> {
     let bot_id: BotId = id.into();
+    check_owner(&app_state, user, bot_id).await?;
 
     let command = DeleteBotCommand { id: bot_id };
     app_state
@@ -272,9 +447,15 @@ async fn fetch_bot_leaderboard(
     Ok(Json(FetchLeaderboardResponse::from(res)))
 }
 
-async fn fetch_bots(State(app_state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
+async fn fetch_bots(
+    State(app_state): State<AppState>,
+    Query(query): Query<FetchBotsQuery>,
+) -> Result<impl IntoResponse, ApiError> {
     let (tx, rx) = oneshot::channel();
-    let command = FetchBotsCommand { response: tx };
+    let command = FetchBotsCommand {
+        author: query.author,
+        response: tx,
+    };
 
     app_state
         .arena_tx
@@ -288,3 +469,107 @@ async fn fetch_bots(State(app_state): State<AppState>) -> Result<impl IntoRespon
         res.into_iter().map(BotMinimalResponse::from).collect_vec(),
     ))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Role;
+    use crate::db::Database;
+    use crate::domain::Bot;
+    use tokio::sync::mpsc;
+
+    async fn start_server(arena_path: &std::path::Path) -> (String, mpsc::Receiver<ArenaCommand>) {
+        crate::arena_server::init(arena_path, false);
+        let (arena_tx, arena_rx) = mpsc::channel(1);
+        let app_state = AppState {
+            arena_tx,
+            db: Database::connect(arena_path).await,
+        };
+        let router = create_router()
+            .with_state(app_state)
+            .layer(Extension(AuthUser {
+                name: "alice".to_string(),
+                role: Role::Member,
+            }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/bots", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        (url, arena_rx)
+    }
+
+    fn request(author: Option<&str>) -> serde_json::Value {
+        serde_json::json!({
+            "name": "bot",
+            "source_code": "print(1)",
+            "language": "python",
+            "author": author,
+        })
+    }
+
+    #[tokio::test]
+    async fn authenticated_user_is_the_author() {
+        let dir = tempfile::tempdir().unwrap();
+        let (url, mut arena_rx) = start_server(dir.path()).await;
+        let http = reqwest::Client::new();
+
+        let res = http
+            .post(&url)
+            .json(&request(Some("bob")))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert!(arena_rx.try_recv().is_err());
+
+        let arena = tokio::spawn(async move {
+            let Some(ArenaCommand::CreateBot(command)) = arena_rx.recv().await else {
+                panic!("expected a bot to be created");
+            };
+            let _ = command.response.send(CreateBotResult::QuotaExceeded);
+            command.author
+        });
+        let res = http.post(&url).json(&request(None)).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert_eq!(arena.await.unwrap().as_deref(), Some("alice"));
+    }
+
+    #[tokio::test]
+    async fn members_manage_only_their_bots() {
+        let dir = tempfile::tempdir().unwrap();
+        let (url, mut arena_rx) = start_server(dir.path()).await;
+        let db = Database::connect(dir.path()).await;
+        let mut ids = Vec::new();
+        for author in ["alice", "bob"] {
+            let mut bot = Bot::new(
+                BotName::try_from(format!("{author}-bot")).unwrap(),
+                SourceCode::try_from("print(1)".to_string()).unwrap(),
+                Language::try_from("python".to_string()).unwrap(),
+                Some(author.to_string()),
+            );
+            db.persist_bot(&mut bot).await;
+            ids.push(i64::from(bot.id));
+        }
+        let http = reqwest::Client::new();
+
+        let res = http
+            .delete(format!("{url}/{}", ids[1]))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let res = http.delete(format!("{url}/999")).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert!(arena_rx.try_recv().is_err());
+
+        let res = http
+            .delete(format!("{url}/{}", ids[0]))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let Some(ArenaCommand::DeleteBot(command)) = arena_rx.recv().await else {
+            panic!("expected the bot to be deleted");
+        };
+        assert_eq!(i64::from(command.id), ids[0]);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::api::errors::ApiError;
+use crate::api::routes::bots::{BotMinimalResponse, BuildResponse};
+use crate::api::AppState;
+use crate::arena::{ArenaCommand, ArenaEvent, MatchFinishedEvent, SubscribeEventsCommand};
+use anyhow::anyhow;
+use axum::extract::State;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use serde::Serialize;
+use tokio::sync::oneshot;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
+
+pub fn create_router() -> Router<AppState> {
+    Router::new().route("/events", get(subscribe_events))
+}
+
+#[derive(Serialize)]
+struct BotDeletedResponse {
+    pub id: i64,
+}
+
+#[derive(Serialize)]
+struct BuildChangedResponse {
+    pub bot_id: i64,
+    #[serde(flatten)]
+    pub build: BuildResponse,
+}
+
+#[derive(Serialize)]
+struct MatchFinishedResponse {
+    pub id: i64,
+    pub seed: i64,
+    pub participants: Vec<MatchFinishedParticipantResponse>,
+}
+
+#[derive(Serialize)]
+struct MatchFinishedParticipantResponse {
+    pub bot_id: i64,
+    pub rank: u8,
+    pub error: bool,
+    pub rating_mu: f64,
+    pub rating_sigma: f64,
+    pub rating_delta: f64,
+}
+
+#[derive(Serialize)]
+struct QueueStatsResponse {
+    pub queued: usize,
+    pub in_progress: usize,
+}
+
+impl From<MatchFinishedEvent> for MatchFinishedResponse {
+    fn from(m: MatchFinishedEvent) -> Self {
+        MatchFinishedResponse {
+            id: m.id.into(),
+            seed: m.seed,
+            participants: m
+                .participants
+                .into_iter()
+                .map(|p| MatchFinishedParticipantResponse {
+                    bot_id: p.bot_id.into(),
+                    rank: p.rank,
+                    error: p.error,
+                    rating_mu: p.rating.mu,
+                    rating_sigma: p.rating.sigma,
+                    rating_delta: p.rating_delta,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// event name is the type of the change, data is its JSON payload
+fn to_sse_event(event: ArenaEvent) -> Result<Event, axum::Error> {
+    match event {
+        ArenaEvent::BotCreated(bot) => Event::default()
+            .event("bot_created")
+            .json_data(BotMinimalResponse::from(bot)),
+        ArenaEvent::BotRenamed(bot) => Event::default()
+            .event("bot_renamed")
+            .json_data(BotMinimalResponse::from(bot)),
+        ArenaEvent::BotArchived(bot) => Event::default()
+            .event("bot_archived")
+            .json_data(BotMinimalResponse::from(bot)),
+        ArenaEvent::BotPaused(bot) => Event::default()
+            .event("bot_paused")
+            .json_data(BotMinimalResponse::from(bot)),
+        ArenaEvent::BotDeleted(id) => Event::default()
+            .event("bot_deleted")
+            .json_data(BotDeletedResponse { id: id.into() }),
+        ArenaEvent::BuildChanged(build) => {
+            Event::default()
+                .event("build_changed")
+                .json_data(BuildChangedResponse {
+                    bot_id: build.bot_id.into(),
+                    build: build.into(),
+                })
+        }
+        ArenaEvent::MatchFinished(m) => Event::default()
+            .event("match_finished")
+            .json_data(MatchFinishedResponse::from(m)),
+        ArenaEvent::QueueStats(stats) => {
+            Event::default()
+                .event("queue_stats")
+                .json_data(QueueStatsResponse {
+                    queued: stats.queued,
+                    in_progress: stats.in_progress,
+                })
+        }
+    }
+}
+
+/// Clients lagging behind get `lagged` event with the number of missed events and should refetch the state.
+async fn subscribe_events(
+    State(app_state): State<AppState>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (tx, rx) = oneshot::channel();
+    let command = SubscribeEventsCommand { response: tx };
+
+    app_state
+        .arena_tx
+        .send(ArenaCommand::SubscribeEvents(command))
+        .await
+        .map_err(|e| anyhow!(e))?;
+
+    let events_rx = rx.await.map_err(|e| anyhow!(e))?;
+
+    let stream = BroadcastStream::new(events_rx).map(|res| match res {
+        Ok(event) => to_sse_event(event),
+        Err(BroadcastStreamRecvError::Lagged(missed)) => {
+            Ok(Event::default().event("lagged").data(missed.to_string()))
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
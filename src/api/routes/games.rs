@@ -0,0 +1,17 @@
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+
+/// Game names are known at startup, so they are served without asking the arenas.
+pub fn create_router(names: Vec<String>) -> Router {
+    let games = names
+        .into_iter()
+        .map(|name| GameResponse { name })
+        .collect::<Vec<_>>();
+    Router::new().route("/games", get(move || async move { Json(games) }))
+}
+
+#[derive(Serialize, Clone)]
+struct GameResponse {
+    pub name: String,
+}
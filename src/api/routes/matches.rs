@@ -0,0 +1,119 @@
+use crate::api::errors::ApiError;
+use crate::api::AppState;
+use crate::domain::{BotId, ErrorKind, Match};
+use anyhow::anyhow;
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+pub fn create_router() -> Router<AppState> {
+    Router::new().route("/matches", get(fetch_matches))
+}
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+const PARAM_PREFIX: &str = "param.";
+
+#[derive(Serialize)]
+struct MatchResponse {
+    pub id: i64,
+    pub seed: i64,
+    pub params: BTreeMap<String, String>,
+    pub participants: Vec<MatchParticipantResponse>,
+}
+
+#[derive(Serialize)]
+struct MatchParticipantResponse {
+    pub bot_id: i64,
+    pub bot_name: String,
+    pub rank: u8,
+    pub error: bool,
+    pub error_kind: Option<&'static str>,
+    pub error_message: Option<String>,
+    pub team: u8,
+    pub score: Option<f64>,
+}
+
+impl MatchResponse {
+    /// participants missing in `bot_names` were deleted meanwhile and are skipped
+    fn new(m: Match, bot_names: &HashMap<BotId, String>) -> Self {
+        MatchResponse {
+            id: m.id.into(),
+            seed: m.seed,
+            params: m.params,
+            participants: m
+                .participants
+                .into_iter()
+                .filter_map(|p| {
+                    Some(MatchParticipantResponse {
+                        bot_id: p.bot_id.into(),
+                        bot_name: bot_names.get(&p.bot_id)?.clone(),
+                        rank: p.rank,
+                        error: p.error,
+                        error_kind: p.error_kind.as_ref().map(ErrorKind::as_str),
+                        error_message: p.error_message,
+                        team: p.team,
+                        score: p.score,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Supported query parameters: `bot_id`, `limit` and `param.<name>` to filter by game parameter value.
+/// Served from the database directly, so it doesn't wait for the arena loop.
+async fn fetch_matches(
+    State(app_state): State<AppState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut bot_id = None;
+    let mut limit = DEFAULT_LIMIT;
+    let mut params = BTreeMap::new();
+    for (key, value) in query {
+        match key.as_str() {
+            "bot_id" => {
+                let id: i64 = value.parse().map_err(|_| {
+                    ApiError::ValidationFailed(anyhow!("bot_id should be a number"))
+                })?;
+                bot_id = Some(id.into());
+            }
+            "limit" => {
+                limit = value
+                    .parse()
+                    .map_err(|_| ApiError::ValidationFailed(anyhow!("limit should be a number")))?;
+                if limit > MAX_LIMIT {
+                    return Err(ApiError::ValidationFailed(anyhow!(
+                        "limit should be at most {MAX_LIMIT}"
+                    )));
+                }
+            }
+            _ => match key.strip_prefix(PARAM_PREFIX) {
+                Some(name) => {
+                    params.insert(name.to_string(), value);
+                }
+                None => {
+                    return Err(ApiError::ValidationFailed(anyhow!(
+                        "unknown query parameter '{key}'"
+                    )))
+                }
+            },
+        }
+    }
+
+    let matches = app_state
+        .db
+        .fetch_latest_matches(bot_id, &params, limit)
+        .await?;
+    let bot_names = app_state.db.fetch_bot_names().await?;
+
+    Ok(Json(
+        matches
+            .into_iter()
+            .map(|m| MatchResponse::new(m, &bot_names))
+            .collect::<Vec<_>>(),
+    ))
+}
@@ -0,0 +1,149 @@
+use crate::api::errors::ApiError;
+use crate::api::routes::bots::MetricResponse;
+use crate::api::AppState;
+use crate::arena::{ArenaCommand, FetchMatrixCommand, FetchMatrixResult, WinDrawLoss};
+use anyhow::anyhow;
+use axum::extract::{Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use tokio::sync::oneshot;
+
+pub fn create_router() -> Router<AppState> {
+    Router::new().route("/matrix", get(fetch_matrix))
+}
+
+#[derive(Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum MatrixFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize)]
+struct FetchMatrixQuery {
+    #[serde(default)]
+    pub format: MatrixFormat,
+}
+
+#[derive(Serialize)]
+struct FetchMatrixResponse {
+    pub bots: Vec<MatrixBotResponse>,
+    pub cells: Vec<Vec<MatrixCellResponse>>,
+}
+
+#[derive(Serialize)]
+struct MatrixBotResponse {
+    pub id: i64,
+    pub name: String,
+    pub metrics: Vec<MetricResponse>,
+}
+
+#[derive(Serialize)]
+struct MatrixCellResponse {
+    pub wins: usize,
+    pub draws: usize,
+    pub loses: usize,
+    pub win_rate: Option<f64>,
+}
+
+impl From<WinDrawLoss> for MatrixCellResponse {
+    fn from(wdl: WinDrawLoss) -> Self {
+        MatrixCellResponse {
+            wins: wdl.wins,
+            draws: wdl.draws,
+            loses: wdl.loses,
+            win_rate: wdl.win_rate(),
+        }
+    }
+}
+
+impl From<FetchMatrixResult> for FetchMatrixResponse {
+    fn from(value: FetchMatrixResult) -> Self {
+        FetchMatrixResponse {
+            bots: value
+                .bots
+                .into_iter()
+                .zip_eq(value.metrics)
+                .map(|(b, metrics)| MatrixBotResponse {
+                    id: b.id.into(),
+                    name: b.name.into(),
+                    metrics: metrics.into_iter().map(Into::into).collect(),
+                })
+                .collect(),
+            cells: value
+                .cells
+                .into_iter()
+                .map(|row| row.into_iter().map(Into::into).collect())
+                .collect(),
+        }
+    }
+}
+
+async fn fetch_matrix(
+    State(app_state): State<AppState>,
+    Query(query): Query<FetchMatrixQuery>,
+) -> Result<Response, ApiError> {
+    let (tx, rx) = oneshot::channel();
+    let command = FetchMatrixCommand { response: tx };
+
+    app_state
+        .arena_tx
+        .send(ArenaCommand::FetchMatrix(command))
+        .await
+        .map_err(|e| anyhow!(e))?;
+
+    let res = rx.await.map_err(|e| anyhow!(e))?;
+
+    let response = match query.format {
+        MatrixFormat::Json => Json(FetchMatrixResponse::from(res)).into_response(),
+        MatrixFormat::Csv => (
+            [
+                (header::CONTENT_TYPE, "text/csv"),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"matrix.csv\"",
+                ),
+            ],
+            to_csv(&res),
+        )
+            .into_response(),
+    };
+    Ok(response)
+}
+
+/// one row per ordered pair of bots, which is easy to pivot in a spreadsheet
+fn to_csv(matrix: &FetchMatrixResult) -> String {
+    let mut csv = String::from("bot,opponent,wins,draws,loses,win_rate\n");
+    for (bot, row) in matrix.bots.iter().zip(&matrix.cells) {
+        for (opponent, wdl) in matrix.bots.iter().zip(row) {
+            if bot.id == opponent.id {
+                continue;
+            }
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{}",
+                csv_field(&bot.name),
+                csv_field(&opponent.name),
+                wdl.wins,
+                wdl.draws,
+                wdl.loses,
+                wdl.win_rate().map(|r| r.to_string()).unwrap_or_default(),
+            );
+        }
+    }
+    csv
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,38 @@
+use crate::arena::ArenaCommand;
+use crate::metrics::{render, GameMetrics, GameSnapshot};
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use std::sync::Arc;
+use tokio::sync::mpsc::Sender;
+
+/// Metrics are read without asking the arenas, so they are available even if an arena loop stalls.
+pub fn create_router(
+    threads: usize,
+    games: Vec<(String, Sender<ArenaCommand>, Arc<GameMetrics>)>,
+) -> Router {
+    let games = Arc::new(games);
+    Router::new().route(
+        "/metrics",
+        get(move || async move { fetch_metrics(threads, &games) }),
+    )
+}
+
+fn fetch_metrics(
+    threads: usize,
+    games: &[(String, Sender<ArenaCommand>, Arc<GameMetrics>)],
+) -> impl IntoResponse {
+    let snapshots = games
+        .iter()
+        .map(|(name, arena_tx, metrics)| GameSnapshot {
+            name,
+            metrics,
+            command_channel_depth: arena_tx.max_capacity() - arena_tx.capacity(),
+        })
+        .collect::<Vec<_>>();
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        render(threads, &snapshots),
+    )
+}
@@ -1 +1,9 @@
+pub mod admin;
 pub mod bots;
+pub mod events;
+pub mod games;
+pub mod matches;
+pub mod matrix;
+pub mod metrics;
+pub mod sprt;
+pub mod status;
@@ -0,0 +1,186 @@
+use crate::api::auth;
+use crate::api::errors::ApiError;
+use crate::api::AppState;
+use crate::arena::{
+    ArenaCommand, BotMinimal, FetchSprtCommand, SprtOverview, StartSprtCommand, StartSprtResult,
+    StopSprtCommand,
+};
+use crate::sprt::{SprtParams, SprtStatus};
+use anyhow::anyhow;
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/sprt", post(start_sprt))
+        .route("/sprt", get(fetch_sprt))
+        .route("/sprt", delete(stop_sprt))
+        .route_layer(axum::middleware::from_fn(auth::require_admin))
+}
+
+#[derive(Deserialize)]
+struct StartSprtRequest {
+    pub bot_a: i64,
+    pub bot_b: i64,
+    pub elo0: f64,
+    pub elo1: f64,
+    #[serde(default = "default_error_probability")]
+    pub alpha: f64,
+    #[serde(default = "default_error_probability")]
+    pub beta: f64,
+}
+
+fn default_error_probability() -> f64 {
+    0.05
+}
+
+#[derive(Serialize)]
+struct SprtResponse {
+    pub bot_a: SprtBotResponse,
+    pub bot_b: SprtBotResponse,
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+    pub wins: usize,
+    pub draws: usize,
+    pub loses: usize,
+    pub llr: f64,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    pub elo_diff: Option<f64>,
+    pub status: &'static str,
+}
+
+#[derive(Serialize)]
+struct SprtBotResponse {
+    pub id: i64,
+    pub name: String,
+}
+
+impl From<BotMinimal> for SprtBotResponse {
+    fn from(value: BotMinimal) -> Self {
+        SprtBotResponse {
+            id: value.id.into(),
+            name: value.name.into(),
+        }
+    }
+}
+
+impl From<SprtOverview> for SprtResponse {
+    fn from(v: SprtOverview) -> Self {
+        SprtResponse {
+            bot_a: v.bot_a.into(),
+            bot_b: v.bot_b.into(),
+            elo0: v.params.elo0,
+            elo1: v.params.elo1,
+            alpha: v.params.alpha,
+            beta: v.params.beta,
+            wins: v.wins,
+            draws: v.draws,
+            loses: v.loses,
+            llr: v.llr,
+            lower_bound: v.lower_bound,
+            upper_bound: v.upper_bound,
+            elo_diff: v.elo_diff,
+            status: match v.status {
+                SprtStatus::Running => "running",
+                SprtStatus::AcceptedH0 => "h0_accepted",
+                SprtStatus::AcceptedH1 => "h1_accepted",
+                SprtStatus::Stopped => "stopped",
+            },
+        }
+    }
+}
+
+async fn start_sprt(
+    State(app_state): State<AppState>,
+    Json(payload): Json<StartSprtRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if payload.elo0 >= payload.elo1 {
+        return Err(ApiError::ValidationFailed(anyhow!(
+            "elo0 should be less than elo1"
+        )));
+    }
+    for p in [payload.alpha, payload.beta] {
+        if !(p > 0.0 && p < 0.5) {
+            return Err(ApiError::ValidationFailed(anyhow!(
+                "alpha and beta should be in 0..0.5 range"
+            )));
+        }
+    }
+
+    let (tx, rx) = oneshot::channel();
+    let command = StartSprtCommand {
+        bot_a: payload.bot_a.into(),
+        bot_b: payload.bot_b.into(),
+        params: SprtParams {
+            elo0: payload.elo0,
+            elo1: payload.elo1,
+            alpha: payload.alpha,
+            beta: payload.beta,
+        },
+        response: tx,
+    };
+
+    app_state
+        .arena_tx
+        .send(ArenaCommand::StartSprt(command))
+        .await
+        .map_err(|e| anyhow!(e))?;
+
+    let res = rx.await.map_err(|e| anyhow!(e))?;
+
+    match res {
+        StartSprtResult::Started(overview) => Ok(Json(SprtResponse::from(*overview))),
+        StartSprtResult::NotFound => Err(ApiError::NotFound),
+        StartSprtResult::SameBot => Err(ApiError::ValidationFailed(anyhow!(
+            "bot_a and bot_b should be different bots"
+        ))),
+        StartSprtResult::UnsupportedPlayerCount => Err(ApiError::ValidationFailed(anyhow!(
+            "SPRT requires game to support 2 players"
+        ))),
+    }
+}
+
+async fn fetch_sprt(State(app_state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
+    let (tx, rx) = oneshot::channel();
+    let command = FetchSprtCommand { response: tx };
+
+    app_state
+        .arena_tx
+        .send(ArenaCommand::FetchSprt(command))
+        .await
+        .map_err(|e| anyhow!(e))?;
+
+    let res = rx.await.map_err(|e| anyhow!(e))?;
+
+    let Some(res) = res else {
+        return Err(ApiError::NotFound);
+    };
+
+    Ok(Json(SprtResponse::from(res)))
+}
+
+async fn stop_sprt(State(app_state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
+    let (tx, rx) = oneshot::channel();
+    let command = StopSprtCommand { response: tx };
+
+    app_state
+        .arena_tx
+        .send(ArenaCommand::StopSprt(command))
+        .await
+        .map_err(|e| anyhow!(e))?;
+
+    let res = rx.await.map_err(|e| anyhow!(e))?;
+
+    let Some(res) = res else {
+        return Err(ApiError::NotFound);
+    };
+
+    Ok(Json(SprtResponse::from(res)))
+}
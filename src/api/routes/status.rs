@@ -0,0 +1,167 @@
+use crate::api::errors::ApiError;
+use crate::api::AppState;
+use crate::arena::{ArenaCommand, FetchStatusCommand, FetchStatusResult, WorkerStatus};
+use crate::config::{BenchmarkConfig, GameConfig, MatchmakingConfig};
+use anyhow::anyhow;
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+pub fn create_router() -> Router<AppState> {
+    Router::new().route("/status", get(fetch_status))
+}
+
+#[derive(Serialize)]
+struct StatusResponse {
+    pub version: &'static str,
+    pub uptime_secs: u64,
+    pub bots: usize,
+    pub archived_bots: usize,
+    pub paused_bots: usize,
+    pub matches: usize,
+    pub builds: BuildsStatusResponse,
+    pub workers: Vec<WorkerStatusResponse>,
+    pub queue_length: usize,
+    pub matchmaking_paused: bool,
+    pub sprt_running: bool,
+    pub matches_per_minute_5m: f64,
+    pub matches_per_minute_60m: f64,
+    pub game: GameConfig,
+    pub matchmaking: MatchmakingStatusResponse,
+}
+
+/// Matchmaking settings with seed counts, seeds loaded from a file can be thousands.
+#[derive(Serialize)]
+struct MatchmakingStatusResponse {
+    pub min_matches: u32,
+    pub min_matches_preference: f64,
+    pub seed_count: Option<usize>,
+    pub benchmarks: Vec<BenchmarkStatusResponse>,
+    pub max_error_rate: Option<f64>,
+    pub error_check_matches: usize,
+    pub max_active_bots_per_author: Option<usize>,
+    pub fair_authors: bool,
+}
+
+#[derive(Serialize)]
+struct BenchmarkStatusResponse {
+    pub name: String,
+    pub seed_count: usize,
+    pub reference_bots: Vec<String>,
+}
+
+impl From<MatchmakingConfig> for MatchmakingStatusResponse {
+    fn from(value: MatchmakingConfig) -> Self {
+        MatchmakingStatusResponse {
+            min_matches: value.min_matches,
+            min_matches_preference: value.min_matches_preference,
+            seed_count: value.seeds.map(|s| s.list().len()),
+            benchmarks: value.benchmarks.into_iter().map(Into::into).collect(),
+            max_error_rate: value.max_error_rate,
+            error_check_matches: value.error_check_matches,
+            max_active_bots_per_author: value.max_active_bots_per_author,
+            fair_authors: value.fair_authors,
+        }
+    }
+}
+
+impl From<BenchmarkConfig> for BenchmarkStatusResponse {
+    fn from(value: BenchmarkConfig) -> Self {
+        BenchmarkStatusResponse {
+            name: value.name,
+            seed_count: value.seeds.list().len(),
+            reference_bots: value.reference_bots,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct BuildsStatusResponse {
+    pub pending: usize,
+    pub running: usize,
+    pub failed: usize,
+}
+
+#[derive(Serialize)]
+struct WorkerStatusResponse {
+    pub name: String,
+    pub threads: u8,
+    pub matches_in_progress: usize,
+}
+
+impl From<WorkerStatus> for WorkerStatusResponse {
+    fn from(value: WorkerStatus) -> Self {
+        WorkerStatusResponse {
+            name: value.name.into(),
+            threads: value.threads,
+            matches_in_progress: value.matches_in_progress,
+        }
+    }
+}
+
+impl From<FetchStatusResult> for StatusResponse {
+    fn from(v: FetchStatusResult) -> Self {
+        StatusResponse {
+            version: env!("CARGO_PKG_VERSION"),
+            uptime_secs: v.uptime.as_secs(),
+            bots: v.bots,
+            archived_bots: v.archived_bots,
+            paused_bots: v.paused_bots,
+            matches: v.matches,
+            builds: BuildsStatusResponse {
+                pending: v.builds_pending,
+                running: v.builds_running,
+                failed: v.builds_failed,
+            },
+            workers: v.workers.into_iter().map(Into::into).collect(),
+            queue_length: v.queue_length,
+            matchmaking_paused: v.matchmaking_paused,
+            sprt_running: v.sprt_running,
+            matches_per_minute_5m: per_minute(v.matches_last_5_min, 5, v.uptime),
+            matches_per_minute_60m: per_minute(v.matches_last_60_min, 60, v.uptime),
+            game: v.game_config,
+            matchmaking: v.matchmaking_config.into(),
+        }
+    }
+}
+
+/// matches are counted since the start, so a window longer than the uptime is only partially filled
+fn per_minute(matches: usize, window_mins: u64, uptime: Duration) -> f64 {
+    let minutes = (window_mins as f64).min(uptime.as_secs_f64() / 60.0);
+    if minutes > 0.0 {
+        matches as f64 / minutes
+    } else {
+        0.0
+    }
+}
+
+async fn fetch_status(State(app_state): State<AppState>) -> Result<impl IntoResponse, ApiError> {
+    let (tx, rx) = oneshot::channel();
+    let command = FetchStatusCommand { response: tx };
+
+    app_state
+        .arena_tx
+        .send(ArenaCommand::FetchStatus(command))
+        .await
+        .map_err(|e| anyhow!(e))?;
+
+    let res = rx.await.map_err(|e| anyhow!(e))?;
+
+    Ok(Json(StatusResponse::from(res)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rate_is_divided_by_uptime_until_the_window_is_filled() {
+        assert_eq!(per_minute(30, 60, Duration::from_secs(10 * 60)), 3.0);
+        assert_eq!(per_minute(30, 5, Duration::from_secs(10 * 60)), 6.0);
+        assert_eq!(per_minute(0, 5, Duration::ZERO), 0.0);
+    }
+}
@@ -1,30 +1,59 @@
-use crate::config::{GameConfig, MatchmakingConfig};
-use crate::db::Database;
-use crate::domain::{Bot, BotId, BotName, Build, Language, Match, Rating, SourceCode, WorkerName};
+use crate::config::{GameConfig, MatchmakingConfig, PermutationRating, RetentionConfig};
+use crate::db::{ComputedStatsRow, Database};
+use crate::domain::{
+    Bot, BotId, BotName, Build, ErrorKind, Language, Match, MatchId, Rating, SourceCode, WorkerName,
+};
 use crate::embedded_worker::{BuildBotInput, EmbeddedWorker, PlayMatchBot, PlayMatchInput};
+use crate::metrics::GameMetrics;
+use crate::notifier::Notifier;
 use crate::ranking::Ranker;
+use crate::sprt::{Sprt, SprtParams, SprtStatus};
+use anyhow::Context;
 use chrono::{DateTime, Utc};
 use itertools::Itertools;
 use rand::prelude::SliceRandom;
 use rand::{thread_rng, Rng};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::cmp::Ordering;
-use std::collections::{HashMap, VecDeque};
-use std::time::Duration;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc::error::TryRecvError;
 use tokio::sync::mpsc::Receiver;
-use tokio::sync::oneshot;
+use tokio::sync::{broadcast, oneshot};
 use tokio_util::sync::CancellationToken;
-use tracing::{instrument, warn};
+use tracing::{info, instrument, warn};
+
+/// slow subscribers lagging behind by more events miss some of them
+const EVENTS_CAPACITY: usize = 256;
+/// how often old matches are pruned according to the retention config
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// how often the stats are persisted, so that only newer matches are replayed at startup
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// values of a custom metric kept per bot to estimate the percentiles
+const METRIC_SAMPLE_SIZE: usize = 256;
+/// failed matches after which a configured seed is excluded, a single failure may be caused by the bots
+const SEED_FAILURE_LIMIT: u32 = 3;
 
 pub enum ArenaCommand {
     CreateBot(CreateBotCommand),
     DeleteBot(DeleteBotCommand),
     RenameBot(RenameBotCommand),
+    ArchiveBot(ArchiveBotCommand),
     FetchLeaderboard(FetchLeaderboardCommand),
     FetchBots(FetchBotsCommand),
+    FetchMatrix(FetchMatrixCommand),
+    StartSprt(StartSprtCommand),
+    StopSprt(StopSprtCommand),
+    FetchSprt(FetchSprtCommand),
+    SubscribeEvents(SubscribeEventsCommand),
+    FetchStatus(FetchStatusCommand),
 }
 
 pub struct FetchBotsCommand {
+    /// only bots of the author if set
+    pub author: Option<String>,
     pub response: oneshot::Sender<Vec<BotMinimal>>,
 }
 
@@ -40,21 +69,53 @@ pub enum RenameBotResult {
     NotFound,
 }
 
+pub struct ArchiveBotCommand {
+    pub id: BotId,
+    pub archived: bool,
+    pub response: oneshot::Sender<ArchiveBotResult>,
+}
+
+pub enum ArchiveBotResult {
+    Updated(BotMinimal),
+    NotFound,
+    /// unarchiving would exceed the active bots limit of the author
+    QuotaExceeded,
+}
+
+#[derive(Clone)]
 pub struct BotMinimal {
     pub id: BotId,
     pub name: BotName,
+    pub archived: bool,
+    pub paused_reason: Option<String>,
+    pub author: Option<String>,
+}
+
+impl From<&Bot> for BotMinimal {
+    fn from(bot: &Bot) -> Self {
+        BotMinimal {
+            id: bot.id,
+            name: bot.name.clone(),
+            archived: bot.archived,
+            paused_reason: bot.paused_reason.clone(),
+            author: bot.author.clone(),
+        }
+    }
 }
 
 pub struct CreateBotCommand {
     pub name: BotName,
     pub source_code: SourceCode,
     pub language: Language,
+    pub author: Option<String>,
     pub response: oneshot::Sender<CreateBotResult>,
 }
 
 pub enum CreateBotResult {
     Created(BotMinimal),
     DuplicateName,
+    /// the author already has the maximum amount of active bots
+    QuotaExceeded,
 }
 
 pub struct DeleteBotCommand {
@@ -78,7 +139,57 @@ pub struct LeaderboardBotOverview {
     pub rating: Rating,
     pub matches_played: usize,
     pub matches_with_error: usize,
+    /// errors of unclassified kind are counted under `None`
+    pub errors_by_kind: Vec<(Option<ErrorKind>, usize)>,
     pub builds: Vec<Build>,
+    pub archived: bool,
+    pub paused_reason: Option<String>,
+    /// results split by seat index, compared to the best opponent in the match
+    pub seats: Vec<WinDrawLoss>,
+    /// averaged over the matches where the referee reported scores
+    pub average_score: Option<f64>,
+    /// own score minus the best opponent's score, averaged like `average_score`
+    pub average_score_margin: Option<f64>,
+    pub metrics: Vec<MetricSummary>,
+}
+
+/// aggregated values of a custom metric reported by the referee
+pub struct MetricSummary {
+    pub name: String,
+    pub count: usize,
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+    /// percentiles are estimated from a sample once there are more than `METRIC_SAMPLE_SIZE` values
+    pub p10: f64,
+    pub p50: f64,
+    pub p90: f64,
+}
+
+impl MetricSummary {
+    fn new(name: String, stats: &MetricStats) -> Self {
+        let sorted = stats
+            .sample
+            .iter()
+            .copied()
+            .sorted_by(f64::total_cmp)
+            .collect_vec();
+        // nearest-rank percentile
+        let percentile = |p: f64| {
+            let rank = (p * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        MetricSummary {
+            name,
+            count: stats.count,
+            mean: stats.sum / stats.count as f64,
+            min: stats.min,
+            max: stats.max,
+            p10: percentile(0.1),
+            p50: percentile(0.5),
+            p90: percentile(0.9),
+        }
+    }
 }
 
 pub struct LeaderboardItem {
@@ -90,18 +201,183 @@ pub struct LeaderboardItem {
     pub loses: usize,
     pub draws: usize,
     pub created_at: DateTime<Utc>,
+    pub archived: bool,
+}
+
+pub struct FetchMatrixCommand {
+    pub response: oneshot::Sender<FetchMatrixResult>,
+}
+
+/// head-to-head results of the non-archived bots, sorted by rating
+pub struct FetchMatrixResult {
+    pub bots: Vec<BotMinimal>,
+    /// `cells[i][j]` is the result of `bots[i]` against `bots[j]`
+    pub cells: Vec<Vec<WinDrawLoss>>,
+    /// `metrics[i]` are the custom metrics of `bots[i]`
+    pub metrics: Vec<Vec<MetricSummary>>,
+}
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize)]
+pub struct WinDrawLoss {
+    pub wins: usize,
+    pub draws: usize,
+    pub loses: usize,
+}
+
+impl WinDrawLoss {
+    /// `ordering` is the comparison of own rank to the opponent's one
+    pub fn record(&mut self, ordering: Ordering) {
+        match ordering {
+            Ordering::Less => self.wins += 1,
+            Ordering::Equal => self.draws += 1,
+            Ordering::Greater => self.loses += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.wins + self.draws + self.loses
+    }
+
+    /// draws count as half a win
+    pub fn win_rate(&self) -> Option<f64> {
+        let total = self.total();
+        (total > 0).then(|| (self.wins as f64 + self.draws as f64 / 2.0) / total as f64)
+    }
+}
+
+pub struct FetchStatusCommand {
+    pub response: oneshot::Sender<FetchStatusResult>,
+}
+
+pub struct FetchStatusResult {
+    pub uptime: Duration,
+    pub bots: usize,
+    pub archived_bots: usize,
+    pub paused_bots: usize,
+    pub matches: usize,
+    pub builds_pending: usize,
+    pub builds_running: usize,
+    pub builds_failed: usize,
+    pub workers: Vec<WorkerStatus>,
+    pub queue_length: usize,
+    /// matchmaking had nothing to schedule last time, e.g. not enough built bots
+    pub matchmaking_paused: bool,
+    pub sprt_running: bool,
+    /// matches finished during the last 5 and 60 minutes, counted since the arena start
+    pub matches_last_5_min: usize,
+    pub matches_last_60_min: usize,
+    pub game_config: GameConfig,
+    pub matchmaking_config: MatchmakingConfig,
+}
+
+pub struct WorkerStatus {
+    pub name: WorkerName,
+    pub threads: u8,
+    pub matches_in_progress: usize,
+}
+
+pub struct SubscribeEventsCommand {
+    pub response: oneshot::Sender<broadcast::Receiver<ArenaEvent>>,
+}
+
+/// changes of the arena state published to the subscribers
+#[derive(Clone)]
+pub enum ArenaEvent {
+    BotCreated(BotMinimal),
+    BotRenamed(BotMinimal),
+    BotArchived(BotMinimal),
+    /// excluded from matchmaking by the arena, see `Bot::paused_reason`
+    BotPaused(BotMinimal),
+    BotDeleted(BotId),
+    BuildChanged(Build),
+    MatchFinished(MatchFinishedEvent),
+    QueueStats(QueueStats),
+}
+
+#[derive(Clone)]
+pub struct MatchFinishedEvent {
+    pub id: MatchId,
+    pub seed: i64,
+    pub participants: Vec<MatchFinishedParticipant>,
+}
+
+#[derive(Clone)]
+pub struct MatchFinishedParticipant {
+    pub bot_id: BotId,
+    pub rank: u8,
+    pub error: bool,
+    pub rating: Rating,
+    /// change of the rating score, permutation groups rated together share it
+    pub rating_delta: f64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct QueueStats {
+    /// matches scheduled but not yet sent to the worker
+    pub queued: usize,
+    /// matches sent to the worker and not finished yet
+    pub in_progress: usize,
+}
+
+pub struct StartSprtCommand {
+    pub bot_a: BotId,
+    pub bot_b: BotId,
+    pub params: SprtParams,
+    pub response: oneshot::Sender<StartSprtResult>,
+}
+
+pub enum StartSprtResult {
+    Started(Box<SprtOverview>),
+    NotFound,
+    SameBot,
+    UnsupportedPlayerCount,
+}
+
+pub struct StopSprtCommand {
+    pub response: oneshot::Sender<Option<SprtOverview>>,
 }
 
+pub struct FetchSprtCommand {
+    pub response: oneshot::Sender<Option<SprtOverview>>,
+}
+
+pub struct SprtOverview {
+    pub bot_a: BotMinimal,
+    pub bot_b: BotMinimal,
+    pub params: SprtParams,
+    pub wins: usize,
+    pub draws: usize,
+    pub loses: usize,
+    pub llr: f64,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    pub elo_diff: Option<f64>,
+    pub status: SprtStatus,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
     game_config: GameConfig,
     matchmaking_config: MatchmakingConfig,
+    retention_config: RetentionConfig,
     ranker: Ranker,
     db: Database,
     worker: EmbeddedWorker,
+    notifier: Notifier,
+    metrics: Arc<GameMetrics>,
     mut commands_rx: Receiver<ArenaCommand>,
     cancellation_token: CancellationToken,
 ) {
-    let mut arena = Arena::new(game_config, matchmaking_config, ranker, db, worker);
+    let mut arena = Arena::new(
+        game_config,
+        matchmaking_config,
+        retention_config,
+        ranker,
+        db,
+        worker,
+        notifier,
+        metrics,
+    );
 
     arena.load_from_db().await;
     arena.reset_stale_builds().await;
@@ -117,7 +393,9 @@ pub async fn run(
         let disconnected = loop {
             match commands_rx.try_recv() {
                 Ok(cmd) => {
+                    let started = Instant::now();
                     arena.handle_command(cmd).await;
+                    arena.metrics.command_latency.observe(started.elapsed());
                 }
                 Err(TryRecvError::Empty) => break false,
                 Err(TryRecvError::Disconnected) => break true,
@@ -130,42 +408,104 @@ pub async fn run(
         // time to let api return responses to clients
         tokio::time::sleep(Duration::from_millis(50)).await;
 
+        let started = Instant::now();
         arena.do_chores().await;
+        arena.metrics.chores_duration.observe(started.elapsed());
     }
+
+    arena.persist_snapshot().await;
 }
 
 struct Arena {
     game_config: GameConfig,
     matchmaking_config: MatchmakingConfig,
+    retention_config: RetentionConfig,
     ranker: Ranker,
     db: Database,
     bots: Vec<Bot>,
+    /// matches not covered by the stats snapshot, older ones are only kept in the db
     matches: Vec<Match>,
+    /// all the matches in the db
+    match_count: usize,
     builds: Vec<Build>,
     worker: EmbeddedWorker,
     computed_stats: ComputedStats,
+    /// stats of the matches which are not replayed, some of them may be pruned
+    stats_snapshot: Option<StatsSnapshot>,
+    /// see [`rating_settings`]
+    rating_settings: String,
+    last_snapshot: Option<Instant>,
+    last_retention: Option<Instant>,
     match_queue: VecDeque<PlayMatchInput>,
+    /// while SPRT is running only the tested bots are matchmade
+    sprt: Option<Sprt>,
+    /// finished matches of the permutation groups waiting for the rest of the group
+    pending_groups: HashMap<i64, PendingGroup>,
+    next_group_id: i64,
+    /// failed matches per configured seed, see `SEED_FAILURE_LIMIT`
+    failed_seeds: HashMap<i64, u32>,
+    /// benchmark matches which were played or scheduled, see `benchmark_key`
+    benchmark_matches: HashSet<(BotId, BotId, i64)>,
+    matches_in_progress: usize,
+    started_at: Instant,
+    /// finish times of the matches during the last hour
+    recently_finished: VecDeque<Instant>,
+    matchmaking_paused: bool,
+    notifier: Notifier,
+    metrics: Arc<GameMetrics>,
+    /// sending fails only when nobody is subscribed, so its result is ignored
+    events_tx: broadcast::Sender<ArenaEvent>,
+    last_queue_stats: QueueStats,
+}
+
+struct PendingGroup {
+    remaining: usize,
+    matches: Vec<Match>,
 }
 
 impl Arena {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         game_config: GameConfig,
         matchmaking_config: MatchmakingConfig,
+        retention_config: RetentionConfig,
         ranker: Ranker,
         db: Database,
         worker: EmbeddedWorker,
+        notifier: Notifier,
+        metrics: Arc<GameMetrics>,
     ) -> Self {
+        let rating_settings = rating_settings(&ranker, game_config.permutation_rating);
         Self {
             game_config,
             matchmaking_config,
+            retention_config,
             ranker,
             db,
             worker,
             bots: Default::default(),
             matches: Default::default(),
+            match_count: 0,
             builds: Default::default(),
             computed_stats: Default::default(),
+            stats_snapshot: None,
+            rating_settings,
+            last_snapshot: None,
+            last_retention: None,
             match_queue: Default::default(),
+            sprt: None,
+            pending_groups: Default::default(),
+            next_group_id: 1,
+            failed_seeds: Default::default(),
+            benchmark_matches: Default::default(),
+            matches_in_progress: 0,
+            started_at: Instant::now(),
+            recently_finished: Default::default(),
+            matchmaking_paused: false,
+            notifier,
+            metrics,
+            events_tx: broadcast::channel(EVENTS_CAPACITY).0,
+            last_queue_stats: Default::default(),
         }
     }
 
@@ -180,14 +520,50 @@ impl Arena {
         // 5. process finished matches
         self.process_finished_matches().await;
 
-        // 7. (future) update views
+        // 6. publish queue stats
+        self.publish_queue_stats();
+
+        // 7. prune old matches
+        self.apply_retention().await;
+
+        // 8. persist stats snapshot
+        if self
+            .last_snapshot
+            .is_none_or(|t| t.elapsed() >= SNAPSHOT_INTERVAL)
+        {
+            self.last_snapshot = Some(Instant::now());
+            self.persist_snapshot().await;
+        }
+
+        // 9. (future) update views
     }
 
     #[instrument(skip(self))]
     pub async fn load_from_db(&mut self) {
         self.bots = self.db.fetch_bots().await;
-        self.matches = self.db.fetch_matches().await;
         self.builds = self.db.fetch_builds().await;
+        self.stats_snapshot = StatsSnapshot::load(&self.db, &self.rating_settings)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("{e:#}. Ratings of the pruned matches are lost.");
+                None
+            });
+        self.matches = self
+            .db
+            .fetch_matches(self.stats_snapshot.as_ref().map(|s| s.last_match_id))
+            .await;
+        self.match_count = self.db.count_matches().await;
+        self.next_group_id = 1 + self.db.fetch_max_group_id().await.unwrap_or_default();
+
+        let benchmark_seeds = self.benchmark_seeds();
+        if !benchmark_seeds.is_empty() {
+            self.benchmark_matches = self
+                .db
+                .fetch_two_player_matchups(&benchmark_seeds)
+                .await
+                .into_iter()
+                .collect();
+        }
     }
 
     pub async fn reset_stale_builds(&mut self) {
@@ -231,6 +607,7 @@ impl Arena {
 
                 build.make_running();
                 self.db.persist_build(build).await;
+                let _ = self.events_tx.send(ArenaEvent::BuildChanged(build.clone()));
                 inputs.push(BuildBotInput {
                     bot_id: bot.id,
                     worker_name: worker_name.clone(),
@@ -258,6 +635,12 @@ impl Arena {
 
             build.make_finished(output.result);
             self.db.persist_build(build).await;
+            let _ = self.events_tx.send(ArenaEvent::BuildChanged(build.clone()));
+            if !build.was_finished_successfully() {
+                if let Some(bot) = self.bots.iter().find(|b| b.id == output.bot_id) {
+                    self.notifier.build_failed(bot.id, &bot.name);
+                }
+            }
         }
     }
 
@@ -267,17 +650,24 @@ impl Arena {
         name: BotName,
         source_code: SourceCode,
         language: Language,
+        author: Option<String>,
     ) -> CreateBotResult {
         if self.bots.iter().any(|b| b.name == name) {
             return CreateBotResult::DuplicateName;
         }
-        let mut bot = Bot::new(name, source_code, language);
+        if author
+            .as_deref()
+            .is_some_and(|a| self.author_quota_reached(a))
+        {
+            return CreateBotResult::QuotaExceeded;
+        }
+        let mut bot = Bot::new(name, source_code, language, author);
         self.db.persist_bot(&mut bot).await;
-        let bot_minimal = BotMinimal {
-            id: bot.id,
-            name: bot.name.clone(),
-        };
+        let bot_minimal = BotMinimal::from(&bot);
         self.bots.push(bot);
+        let _ = self
+            .events_tx
+            .send(ArenaEvent::BotCreated(bot_minimal.clone()));
         CreateBotResult::Created(bot_minimal)
     }
 
@@ -293,35 +683,101 @@ impl Arena {
 
         bot.name = new_name;
         self.db.persist_bot(bot).await;
-        let bot_minimal = BotMinimal {
-            id: bot.id,
-            name: bot.name.clone(),
-        };
+        let bot_minimal = BotMinimal::from(&*bot);
+        let _ = self
+            .events_tx
+            .send(ArenaEvent::BotRenamed(bot_minimal.clone()));
         RenameBotResult::Renamed(bot_minimal)
     }
 
+    #[instrument(skip(self))]
+    async fn cmd_archive_bot(&mut self, id: BotId, archived: bool) -> ArchiveBotResult {
+        let Some(bot) = self.bots.iter().find(|b| b.id == id) else {
+            return ArchiveBotResult::NotFound;
+        };
+        if bot.archived
+            && !archived
+            && bot
+                .author
+                .as_deref()
+                .is_some_and(|a| self.author_quota_reached(a))
+        {
+            return ArchiveBotResult::QuotaExceeded;
+        }
+
+        let bot = self
+            .bots
+            .iter_mut()
+            .find(|b| b.id == id)
+            .expect("bot is checked to exist");
+
+        bot.archived = archived;
+        if !archived {
+            bot.paused_reason = None;
+        }
+        self.db.persist_bot(bot).await;
+        let bot_minimal = BotMinimal::from(&*bot);
+        let _ = self
+            .events_tx
+            .send(ArenaEvent::BotArchived(bot_minimal.clone()));
+        ArchiveBotResult::Updated(bot_minimal)
+    }
+
     #[instrument(skip(self))]
     async fn cmd_delete_bot(&mut self, id: BotId) {
         // builds would be automatically deleted by foreign link constraint
         // participations would be automatically deleted by foreign link constraint
         // matches would be automatically delete by db trigger
+        if let Some(sprt) = self.sprt.as_mut().filter(|s| s.involves(id)) {
+            sprt.stop();
+        }
         self.db.delete_bot(id).await;
+        self.match_count = self.db.count_matches().await;
+        match self.stats_snapshot.take() {
+            // without pruned matches the stats are recalculated exactly, as if the bot never played
+            Some(snapshot) if !snapshot.has_pruned => {
+                self.db.delete_stats_snapshot().await;
+                // matches of the pending groups are stored already, but rated once the group finishes
+                let pending: HashSet<MatchId> = self
+                    .pending_groups
+                    .values()
+                    .flat_map(|g| &g.matches)
+                    .map(|m| m.id)
+                    .collect();
+                self.matches = self.db.fetch_matches(None).await;
+                self.matches.retain(|m| !pending.contains(&m.id));
+            }
+            Some(mut snapshot) => {
+                snapshot.stats.remove_bot(id);
+                self.db.persist_stats_snapshot(&snapshot.to_row()).await;
+                self.stats_snapshot = Some(snapshot);
+            }
+            None => {}
+        }
         self.bots.retain(|bot| bot.id != id);
         self.matches
             .retain(|m| !m.participants.iter().any(|p| p.bot_id == id));
         self.builds.retain(|b| b.bot_id != id);
+        self.pending_groups.retain(|_, g| {
+            !g.matches
+                .iter()
+                .any(|m| m.participants.iter().any(|p| p.bot_id == id))
+        });
         self.recalculate_computed_full();
+        // all the matches were reloaded for the recalculation, only the uncovered ones are kept
+        if self.stats_snapshot.is_none() {
+            self.persist_snapshot().await;
+        }
+        let _ = self.events_tx.send(ArenaEvent::BotDeleted(id));
     }
 
     #[instrument(skip(self), level = "debug")]
-    async fn cmd_fetch_bots(&mut self) -> Vec<BotMinimal> {
+    async fn cmd_fetch_bots(&mut self, author: Option<String>) -> Vec<BotMinimal> {
         let mut bots = self
             .bots
             .iter()
-            .map(|b| BotMinimal {
-                id: b.id,
-                name: b.name.clone(),
-            })
+            .filter(|b| author.is_none() || b.author == author)
+            .map(BotMinimal::from)
             .collect_vec();
         // sort+rev so that bot with the biggest id is first in the list
         bots.sort_by_key::<i64, _>(|b| b.id.into());
@@ -340,12 +796,28 @@ impl Arena {
             rating: self.rating(target.id),
             matches_played: self.matches_played(target.id),
             matches_with_error: self.matches_with_error(target.id),
+            errors_by_kind: self
+                .computed_stats
+                .errors_by_kind
+                .iter()
+                .filter(|((id, _), _)| *id == target.id)
+                .map(|((_, kind), cnt)| (*kind, *cnt))
+                .sorted_by_key(|(kind, _)| kind.map(|k| k.as_str()))
+                .collect(),
             builds: self
                 .builds
                 .iter()
                 .filter(|b| b.bot_id == target_id)
                 .cloned()
                 .collect(),
+            archived: target.archived,
+            paused_reason: target.paused_reason.clone(),
+            seats: (0..(self.game_config.max_players * self.game_config.team_size) as usize)
+                .map(|index| self.seat_results(target.id, index))
+                .collect(),
+            average_score: self.score_stats(target.id).average_score(),
+            average_score_margin: self.score_stats(target.id).average_margin(),
+            metrics: self.metric_summaries(target.id),
         };
 
         let mut items = Vec::with_capacity(self.bots.len());
@@ -357,37 +829,18 @@ impl Arena {
                 .filter(|b| rating.score() < self.rating(b.id).score())
                 .count();
 
-            let mut wins = 0;
-            let mut loses = 0;
-            let mut draws = 0;
-
-            if target_id != bot.id {
-                for m in &self.matches {
-                    let Some(p_target) = m.participants.iter().find(|p| p.bot_id == target_id)
-                    else {
-                        continue;
-                    };
-                    let Some(p_current) = m.participants.iter().find(|p| p.bot_id == bot.id) else {
-                        continue;
-                    };
-
-                    match p_target.rank.cmp(&p_current.rank) {
-                        Ordering::Less => wins += 1,
-                        Ordering::Equal => draws += 1,
-                        Ordering::Greater => loses += 1,
-                    }
-                }
-            }
+            let wdl = self.head_to_head(target_id, bot.id);
 
             let item = LeaderboardItem {
                 id: bot.id,
                 rank: 1 + stronger_bots_cnt,
                 name: bot.name.clone(),
                 rating,
-                wins,
-                loses,
-                draws,
+                wins: wdl.wins,
+                loses: wdl.loses,
+                draws: wdl.draws,
                 created_at: bot.created_at,
+                archived: bot.archived,
             };
             items.push(item);
         }
@@ -398,11 +851,154 @@ impl Arena {
         })
     }
 
+    #[instrument(skip(self), level = "debug")]
+    fn cmd_fetch_matrix(&self) -> FetchMatrixResult {
+        let bots = self
+            .bots
+            .iter()
+            .filter(|b| !b.archived)
+            .sorted_by(|a, b| {
+                self.rating(b.id)
+                    .score()
+                    .total_cmp(&self.rating(a.id).score())
+            })
+            .collect_vec();
+
+        let cells = bots
+            .iter()
+            .map(|row| {
+                bots.iter()
+                    .map(|col| self.head_to_head(row.id, col.id))
+                    .collect()
+            })
+            .collect();
+
+        let metrics = bots.iter().map(|b| self.metric_summaries(b.id)).collect();
+
+        FetchMatrixResult {
+            bots: bots.into_iter().map(BotMinimal::from).collect(),
+            cells,
+            metrics,
+        }
+    }
+
+    #[instrument(skip(self))]
+    fn cmd_start_sprt(
+        &mut self,
+        bot_a: BotId,
+        bot_b: BotId,
+        params: SprtParams,
+    ) -> StartSprtResult {
+        if bot_a == bot_b {
+            return StartSprtResult::SameBot;
+        }
+        if !self.bots.iter().any(|b| b.id == bot_a) || !self.bots.iter().any(|b| b.id == bot_b) {
+            return StartSprtResult::NotFound;
+        }
+        if !(self.game_config.min_players..=self.game_config.max_players).contains(&2)
+            || self.game_config.team_size != 1
+        {
+            return StartSprtResult::UnsupportedPlayerCount;
+        }
+
+        // matches scheduled before the test would only delay it
+        let dropped = self.match_queue.drain(..).collect_vec();
+        for m in &dropped {
+            if let [a, b] = m.bots.as_slice() {
+                self.benchmark_matches
+                    .remove(&benchmark_key(a.bot_id, b.bot_id, m.seed));
+            }
+        }
+        let dropped_groups = dropped
+            .iter()
+            .filter_map(|m| m.group_id)
+            .unique()
+            .collect_vec();
+        for group_id in dropped_groups {
+            self.finish_group(group_id);
+        }
+        self.sprt = Some(Sprt::new(bot_a, bot_b, params));
+        let overview = self
+            .cmd_fetch_sprt()
+            .expect("both bots were checked to exist");
+        StartSprtResult::Started(Box::new(overview))
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    fn cmd_stop_sprt(&mut self) -> Option<SprtOverview> {
+        self.sprt.as_mut()?.stop();
+        self.cmd_fetch_sprt()
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    fn cmd_fetch_status(&self) -> FetchStatusResult {
+        let finished_since = |mins: u64| {
+            self.recently_finished
+                .iter()
+                .filter(|t| t.elapsed() <= Duration::from_secs(mins * 60))
+                .count()
+        };
+        FetchStatusResult {
+            uptime: self.started_at.elapsed(),
+            bots: self.bots.len(),
+            archived_bots: self.bots.iter().filter(|b| b.archived).count(),
+            paused_bots: self
+                .bots
+                .iter()
+                .filter(|b| b.paused_reason.is_some())
+                .count(),
+            matches: self.match_count,
+            builds_pending: self.builds.iter().filter(|b| b.is_pending()).count(),
+            builds_running: self.builds.iter().filter(|b| b.is_running()).count(),
+            builds_failed: self.builds.iter().filter(|b| b.has_failed()).count(),
+            workers: vec![WorkerStatus {
+                name: WorkerName::embedded(),
+                threads: self.worker.config.threads,
+                matches_in_progress: self.matches_in_progress,
+            }],
+            queue_length: self.match_queue.len(),
+            matchmaking_paused: self.matchmaking_paused,
+            sprt_running: self.sprt.as_ref().is_some_and(|s| s.is_running()),
+            matches_last_5_min: finished_since(5),
+            matches_last_60_min: finished_since(60),
+            game_config: self.game_config.clone(),
+            matchmaking_config: self.matchmaking_config.clone(),
+        }
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    fn cmd_fetch_sprt(&self) -> Option<SprtOverview> {
+        self.sprt.as_ref().and_then(|s| self.sprt_overview(s))
+    }
+
+    fn sprt_overview(&self, sprt: &Sprt) -> Option<SprtOverview> {
+        let bot_minimal = |id| self.bots.iter().find(|b| b.id == id).map(BotMinimal::from);
+        let (lower_bound, upper_bound) = sprt.bounds();
+        Some(SprtOverview {
+            bot_a: bot_minimal(sprt.bot_a)?,
+            bot_b: bot_minimal(sprt.bot_b)?,
+            params: sprt.params,
+            wins: sprt.wins,
+            draws: sprt.draws,
+            loses: sprt.loses,
+            llr: sprt.llr(),
+            lower_bound,
+            upper_bound,
+            elo_diff: sprt.elo_diff(),
+            status: sprt.status,
+        })
+    }
+
     pub async fn handle_command(&mut self, command: ArenaCommand) {
         match command {
             ArenaCommand::CreateBot(command) => {
                 let res = self
-                    .cmd_create_bot(command.name, command.source_code, command.language)
+                    .cmd_create_bot(
+                        command.name,
+                        command.source_code,
+                        command.language,
+                        command.author,
+                    )
                     .await;
                 if command.response.send(res).is_err() {
                     warn!("Failed to send response to client");
@@ -417,8 +1013,14 @@ impl Arena {
                     warn!("Failed to send response to client");
                 }
             }
+            ArenaCommand::ArchiveBot(command) => {
+                let res = self.cmd_archive_bot(command.id, command.archived).await;
+                if command.response.send(res).is_err() {
+                    warn!("Failed to send response to client");
+                }
+            }
             ArenaCommand::FetchBots(command) => {
-                let res = self.cmd_fetch_bots().await;
+                let res = self.cmd_fetch_bots(command.author).await;
                 if command.response.send(res).is_err() {
                     warn!("Failed to send response to client");
                 }
@@ -429,6 +1031,42 @@ impl Arena {
                     warn!("Failed to send response to client");
                 }
             }
+            ArenaCommand::FetchMatrix(command) => {
+                let res = self.cmd_fetch_matrix();
+                if command.response.send(res).is_err() {
+                    warn!("Failed to send response to client");
+                }
+            }
+            ArenaCommand::StartSprt(command) => {
+                let res = self.cmd_start_sprt(command.bot_a, command.bot_b, command.params);
+                if command.response.send(res).is_err() {
+                    warn!("Failed to send response to client");
+                }
+            }
+            ArenaCommand::StopSprt(command) => {
+                let res = self.cmd_stop_sprt();
+                if command.response.send(res).is_err() {
+                    warn!("Failed to send response to client");
+                }
+            }
+            ArenaCommand::FetchStatus(command) => {
+                let res = self.cmd_fetch_status();
+                if command.response.send(res).is_err() {
+                    warn!("Failed to send response to client");
+                }
+            }
+            ArenaCommand::SubscribeEvents(command) => {
+                let res = self.events_tx.subscribe();
+                if command.response.send(res).is_err() {
+                    warn!("Failed to send response to client");
+                }
+            }
+            ArenaCommand::FetchSprt(command) => {
+                let res = self.cmd_fetch_sprt();
+                if command.response.send(res).is_err() {
+                    warn!("Failed to send response to client");
+                }
+            }
         }
     }
 
@@ -437,9 +1075,25 @@ impl Arena {
         let mm_match_queue_size_threshold = self.worker.config.threads as usize * 2;
 
         while self.match_queue.len() < mm_match_queue_size_threshold {
-            let Some(new_matches) = self.schedule_match() else {
+            let Some(mut new_matches) = self.schedule_match() else {
+                self.matchmaking_paused = true;
                 break;
             };
+            self.matchmaking_paused = false;
+            if self.game_config.permutation_rating == PermutationRating::SumRanks
+                && new_matches.len() > 1
+            {
+                let group_id = self.next_group_id;
+                self.next_group_id += 1;
+                for m in &mut new_matches {
+                    m.group_id = Some(group_id);
+                }
+                let group = PendingGroup {
+                    remaining: new_matches.len(),
+                    matches: Vec::with_capacity(new_matches.len()),
+                };
+                self.pending_groups.insert(group_id, group);
+            }
             self.match_queue.extend(new_matches);
         }
 
@@ -447,6 +1101,7 @@ impl Arena {
             match self.worker.match_tx.try_reserve() {
                 Ok(permit) => {
                     permit.send(input);
+                    self.matches_in_progress += 1;
                 }
                 Err(_) => {
                     self.match_queue.push_front(input);
@@ -458,26 +1113,276 @@ impl Arena {
 
     #[instrument(skip(self), level = "debug")]
     pub async fn process_finished_matches(&mut self) {
+        if self.worker.match_result_rx.is_empty() {
+            return;
+        }
+        let played_before = self.computed_stats.matches_played.clone();
+        let errors_before = self.computed_stats.matches_with_error.clone();
+        let leader_before = self.leader();
+
+        let mut finished = vec![];
         while let Ok(output) = self.worker.match_result_rx.try_recv() {
+            self.matches_in_progress -= 1;
+            let participants = match output.result {
+                Ok(participants) => participants,
+                Err(_) => {
+                    self.metrics.failed_matches.inc();
+                    self.record_seed_failure(output.seed);
+                    if let Some(group_id) = output.group_id {
+                        self.skip_group_match(group_id);
+                    }
+                    continue;
+                }
+            };
+
             // validation
-            if output
-                .participants
+            if participants
                 .iter()
                 .any(|p| self.bots.iter().all(|b| b.id != p.bot_id))
             {
                 warn!(
                     "Match participant was deleted while match was running, ignoring match results"
                 );
+                if let Some(group_id) = output.group_id {
+                    self.pending_groups.remove(&group_id);
+                }
                 continue;
             }
 
-            let mut new_match = Match::new(output.seed, output.participants);
-            self.db.persist_match(&mut new_match).await;
-            self.matches.push(new_match);
+            let mut new_match = Match::new(output.seed, participants);
+            new_match.group_id = output.group_id;
+            new_match.params = output.params;
+            finished.push(new_match);
+        }
 
-            self.computed_stats
-                .recalc_after_matches(&self.ranker, self.matches.last().into_iter());
+        // a transaction per match is too slow at high throughput
+        self.db.persist_matches(&mut finished).await;
+        self.match_count += finished.len();
+
+        let now = Instant::now();
+        for new_match in finished {
+            self.record_finish_time(now);
+            self.metrics.matches.inc();
+            self.metrics
+                .participations
+                .inc_by(new_match.participants.len() as u64);
+            self.metrics
+                .participation_errors
+                .inc_by(new_match.participants.iter().filter(|p| p.error).count() as u64);
+
+            if let Some(sprt) = self.sprt.as_mut().filter(|s| s.is_running()) {
+                sprt.record(&new_match);
+                if !sprt.is_running() {
+                    info!(
+                        "SPRT finished with {:?} after {} matches (llr = {:.3})",
+                        sprt.status,
+                        sprt.wins + sprt.draws + sprt.loses,
+                        sprt.llr()
+                    );
+                }
+            }
+
+            let Some(group_id) = new_match.group_id else {
+                self.matches.push(new_match);
+                self.rate_new_matches(self.matches.len() - 1);
+                continue;
+            };
+
+            // permutations are rated together once the whole group is finished
+            let group = self
+                .pending_groups
+                .entry(group_id)
+                .or_insert_with(|| PendingGroup {
+                    remaining: 1,
+                    matches: vec![],
+                });
+            group.matches.push(new_match);
+            group.remaining -= 1;
+            if group.remaining == 0 {
+                self.finish_group(group_id);
+            }
         }
+
+        self.notify_progress(&played_before, &errors_before, leader_before);
+        self.pause_failing_bots(&played_before).await;
+    }
+
+    fn notify_progress(
+        &self,
+        played_before: &HashMap<BotId, usize>,
+        errors_before: &HashMap<BotId, usize>,
+        leader_before: Option<BotId>,
+    ) {
+        for bot in &self.bots {
+            let played = played_before.get(&bot.id).copied().unwrap_or_default();
+            let errors = errors_before.get(&bot.id).copied().unwrap_or_default();
+            if played == self.matches_played(bot.id) {
+                continue;
+            }
+            self.notifier
+                .matches_played(bot.id, &bot.name, played, self.matches_played(bot.id));
+            self.notifier.error_rate(
+                bot.id,
+                &bot.name,
+                (played, self.matches_played(bot.id)),
+                (errors, self.matches_with_error(bot.id)),
+            );
+        }
+
+        let leader = self.leader();
+        if leader != leader_before {
+            if let Some(bot) = self.bots.iter().find(|b| Some(b.id) == leader) {
+                self.notifier.new_leader(bot.id, &bot.name);
+            }
+        }
+    }
+
+    /// non-archived bot with the best rating among the ones which played
+    fn leader(&self) -> Option<BotId> {
+        self.bots
+            .iter()
+            .filter(|b| !b.archived && self.matches_played(b.id) > 0)
+            .max_by(|a, b| {
+                self.rating(a.id)
+                    .score()
+                    .total_cmp(&self.rating(b.id).score())
+            })
+            .map(|b| b.id)
+    }
+
+    /// a bot is checked once, when its played matches reach `error_check_matches`
+    async fn pause_failing_bots(&mut self, played_before: &HashMap<BotId, usize>) {
+        let Some(max_error_rate) = self.matchmaking_config.max_error_rate else {
+            return;
+        };
+        let check_at = self.matchmaking_config.error_check_matches;
+
+        let failing = self
+            .bots
+            .iter()
+            .filter(|b| b.is_matchmade())
+            .filter(|b| {
+                let before = played_before.get(&b.id).copied().unwrap_or_default();
+                before < check_at && check_at <= self.matches_played(b.id)
+            })
+            .filter(|b| {
+                let error_rate =
+                    self.matches_with_error(b.id) as f64 / self.matches_played(b.id) as f64;
+                error_rate > max_error_rate
+            })
+            .map(|b| (b.id, b.name.clone()))
+            .collect_vec();
+
+        for (id, name) in failing {
+            let reason = format!(
+                "failed in {} of {} matches",
+                self.matches_with_error(id),
+                self.matches_played(id)
+            );
+            warn!(
+                "Bot '{}' {reason}, it is paused and excluded from matchmaking",
+                &*name
+            );
+            let bot = self
+                .bots
+                .iter_mut()
+                .find(|b| b.id == id)
+                .expect("failing bot exists");
+            bot.paused_reason = Some(reason);
+            self.db.persist_bot(bot).await;
+            let _ = self
+                .events_tx
+                .send(ArenaEvent::BotPaused(BotMinimal::from(&*bot)));
+        }
+    }
+
+    /// match of the group won't be finished, the rest of the group is rated without it
+    fn skip_group_match(&mut self, group_id: i64) {
+        let Some(group) = self.pending_groups.get_mut(&group_id) else {
+            return;
+        };
+        group.remaining -= 1;
+        if group.remaining == 0 {
+            self.finish_group(group_id);
+        }
+    }
+
+    fn finish_group(&mut self, group_id: i64) {
+        let Some(group) = self.pending_groups.remove(&group_id) else {
+            return;
+        };
+        let start = self.matches.len();
+        self.matches.extend(group.matches);
+        self.rate_new_matches(start);
+    }
+
+    /// rates the matches starting from `start` index and publishes them
+    fn rate_new_matches(&mut self, start: usize) {
+        let ratings_before: HashMap<BotId, Rating> = self.matches[start..]
+            .iter()
+            .flat_map(|m| &m.participants)
+            .map(|p| (p.bot_id, self.rating(p.bot_id)))
+            .collect();
+
+        self.computed_stats.recalc_after_matches(
+            &self.ranker,
+            self.matches[start..].iter(),
+            self.combine_groups(),
+        );
+
+        for m in &self.matches[start..] {
+            let participants = m
+                .participants
+                .iter()
+                .map(|p| {
+                    let rating = self.rating(p.bot_id);
+                    MatchFinishedParticipant {
+                        bot_id: p.bot_id,
+                        rank: p.rank,
+                        error: p.error,
+                        rating,
+                        rating_delta: rating.score() - ratings_before[&p.bot_id].score(),
+                    }
+                })
+                .collect();
+            let event = MatchFinishedEvent {
+                id: m.id,
+                seed: m.seed,
+                participants,
+            };
+            let _ = self.events_tx.send(ArenaEvent::MatchFinished(event));
+        }
+    }
+
+    fn record_finish_time(&mut self, now: Instant) {
+        self.recently_finished.push_back(now);
+        while self
+            .recently_finished
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > Duration::from_secs(60 * 60))
+        {
+            self.recently_finished.pop_front();
+        }
+    }
+
+    /// the event is published only when the stats changed
+    fn publish_queue_stats(&mut self) {
+        let stats = QueueStats {
+            queued: self.match_queue.len(),
+            in_progress: self.matches_in_progress,
+        };
+        self.metrics.match_queue_length.set(stats.queued as i64);
+        self.metrics
+            .matches_in_progress
+            .set(stats.in_progress as i64);
+        if stats != self.last_queue_stats {
+            self.last_queue_stats = stats;
+            let _ = self.events_tx.send(ArenaEvent::QueueStats(stats));
+        }
+    }
+
+    fn combine_groups(&self) -> bool {
+        self.game_config.permutation_rating == PermutationRating::SumRanks
     }
 
     fn is_bot_ready_for_playing(&self, id: BotId) -> bool {
@@ -496,17 +1401,65 @@ impl Arena {
         true
     }
 
-    fn schedule_match(&self) -> Option<Vec<PlayMatchInput>> {
+    /// whether the author has `max_active_bots_per_author` non-archived bots already
+    fn author_quota_reached(&self, author: &str) -> bool {
+        let Some(max) = self.matchmaking_config.max_active_bots_per_author else {
+            return false;
+        };
+        let active = self
+            .bots
+            .iter()
+            .filter(|b| !b.archived && b.author.as_deref() == Some(author))
+            .count();
+        active >= max
+    }
+
+    /// uniformly random bot or, with `fair_authors`, a random bot of a uniformly random author,
+    /// bots without an author are grouped together
+    fn pick_bot(&self, bot_ids: &[BotId], rng: &mut impl Rng) -> BotId {
+        if !self.matchmaking_config.fair_authors {
+            return bot_ids[rng.gen_range(0..bot_ids.len())];
+        }
+        let author = |id: &BotId| {
+            self.bots
+                .iter()
+                .find(|b| b.id == *id)
+                .and_then(|b| b.author.as_deref())
+        };
+        let authors = bot_ids.iter().map(author).unique().collect_vec();
+        let chosen = authors[rng.gen_range(0..authors.len())];
+        let candidates = bot_ids
+            .iter()
+            .filter(|id| author(id) == chosen)
+            .collect_vec();
+        *candidates[rng.gen_range(0..candidates.len())]
+    }
+
+    fn schedule_match(&mut self) -> Option<Vec<PlayMatchInput>> {
+        if let Some(sprt) = self.sprt.as_ref().filter(|s| s.is_running()) {
+            return self.schedule_sprt_match(sprt);
+        }
+
         let mut rng = thread_rng();
 
+        if let Some(key) = self.next_benchmark_match() {
+            self.benchmark_matches.insert(key);
+            let (a, b, seed) = key;
+            let mut players = vec![a, b];
+            players.shuffle(&mut rng);
+            return Some(self.create_play_match_inputs(players, seed));
+        }
+
         let bot_ids = self
             .bots
             .iter()
+            .filter(|b| b.is_matchmade())
             .map(|b| b.id)
             .filter(|id| self.is_bot_ready_for_playing(*id))
             .collect_vec();
 
-        if bot_ids.len() < self.game_config.min_players as usize {
+        let team_size = self.game_config.team_size as usize;
+        if bot_ids.len() < self.game_config.min_players as usize * team_size {
             return None;
         }
 
@@ -519,18 +1472,19 @@ impl Arena {
         let first_bot_id = if !bot_ids_min_matches.is_empty()
             && rng.gen::<f64>() < self.matchmaking_config.min_matches_preference
         {
-            bot_ids_min_matches[rng.gen_range(0..bot_ids_min_matches.len())]
+            self.pick_bot(&bot_ids_min_matches, &mut rng)
         } else {
-            bot_ids[rng.gen_range(0..bot_ids.len())]
+            self.pick_bot(&bot_ids, &mut rng)
         };
 
-        let n_players =
-            rng.gen_range(self.game_config.min_players..=self.game_config.max_players) as usize;
+        let max_teams = (self.game_config.max_players as usize).min(bot_ids.len() / team_size);
+        let n_teams = rng.gen_range(self.game_config.min_players as usize..=max_teams);
+        let n_players = n_teams * team_size;
         let mut players = Vec::with_capacity(n_players);
         players.push(first_bot_id);
         while players.len() < n_players {
             let next_bot_id = loop {
-                let candidate_id = bot_ids[rng.gen_range(0..bot_ids.len())];
+                let candidate_id = self.pick_bot(&bot_ids, &mut rng);
                 if !players.contains(&candidate_id) {
                     break candidate_id;
                 }
@@ -539,45 +1493,290 @@ impl Arena {
             players.push(next_bot_id);
         }
         players.shuffle(&mut rng);
-        let scheduled_match = PlayMatchInput {
-            seed: rng.gen(),
-            bots: players
-                .into_iter()
-                .map(|id| PlayMatchBot {
-                    bot_id: id,
-                    language: self
-                        .bots
-                        .iter()
-                        .find(|b| b.id == id)
-                        .unwrap()
-                        .language
-                        .clone(),
-                })
-                .collect(),
+        let seed = self.random_seed(&mut rng)?;
+        Some(self.create_play_match_inputs(players, seed))
+    }
+
+    fn schedule_sprt_match(&self, sprt: &Sprt) -> Option<Vec<PlayMatchInput>> {
+        if !self.is_bot_ready_for_playing(sprt.bot_a) || !self.is_bot_ready_for_playing(sprt.bot_b)
+        {
+            return None;
+        }
+
+        let mut rng = thread_rng();
+        let mut players = vec![sprt.bot_a, sprt.bot_b];
+        players.shuffle(&mut rng);
+        let seed = self.random_seed(&mut rng)?;
+        Some(self.create_play_match_inputs(players, seed))
+    }
+
+    /// picks from the configured seeds if any, `None` if all of them crashed the referee
+    fn random_seed(&self, rng: &mut impl Rng) -> Option<i64> {
+        let Some(seeds) = &self.matchmaking_config.seeds else {
+            return Some(rng.gen());
         };
+        let seeds = seeds
+            .list()
+            .iter()
+            .filter(|s| !self.is_seed_excluded(**s))
+            .collect_vec();
+        seeds.choose(rng).map(|s| **s)
+    }
 
-        let res = if self.game_config.symmetric {
-            vec![scheduled_match]
-        } else {
-            let n = scheduled_match.bots.len();
-            scheduled_match
+    /// first benchmark match not yet played by a bot against a reference bot
+    fn next_benchmark_match(&self) -> Option<(BotId, BotId, i64)> {
+        for benchmark in &self.matchmaking_config.benchmarks {
+            let references = benchmark
+                .reference_bots
+                .iter()
+                .filter_map(|name| self.bots.iter().find(|b| &*b.name == name))
+                .map(|b| b.id)
+                .filter(|id| self.is_bot_ready_for_playing(*id))
+                .collect_vec();
+
+            let bot_ids = self
                 .bots
-                .into_iter()
-                .permutations(n)
-                .map(|p| PlayMatchInput {
-                    seed: scheduled_match.seed,
-                    bots: p,
-                })
-                .collect()
+                .iter()
+                .filter(|b| b.is_matchmade() && !references.contains(&b.id))
+                .map(|b| b.id)
+                .filter(|id| self.is_bot_ready_for_playing(*id));
+
+            for bot_id in bot_ids {
+                for &reference_id in &references {
+                    for &seed in benchmark.seeds.list() {
+                        let key = benchmark_key(bot_id, reference_id, seed);
+                        if !self.is_seed_excluded(seed) && !self.benchmark_matches.contains(&key) {
+                            return Some(key);
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// `players` are in seat order, consecutive 'team_size' players form a team.
+    /// For asymmetric games every seat permutation of the teams is played with the same seed and params.
+    fn create_play_match_inputs(&self, players: Vec<BotId>, seed: i64) -> Vec<PlayMatchInput> {
+        let params = self.game_config.sample_params(&mut thread_rng());
+        let teams = players
+            .chunks(self.game_config.team_size as usize)
+            .map(|team| team.to_vec())
+            .collect_vec();
+
+        let seatings = if self.game_config.symmetric {
+            vec![teams]
+        } else {
+            let n = teams.len();
+            teams.into_iter().permutations(n).collect()
         };
-        Some(res)
+
+        seatings
+            .into_iter()
+            .map(|teams| PlayMatchInput {
+                seed,
+                group_id: None,
+                params: params.clone(),
+                bots: teams
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(|(team, bots)| bots.into_iter().map(move |id| (team as u8, id)))
+                    .map(|(team, id)| PlayMatchBot {
+                        bot_id: id,
+                        language: self
+                            .bots
+                            .iter()
+                            .find(|b| b.id == id)
+                            .unwrap()
+                            .language
+                            .clone(),
+                        team,
+                    })
+                    .collect(),
+            })
+            .collect()
     }
 
     #[instrument(skip(self))]
     fn recalculate_computed_full(&mut self) {
-        self.computed_stats.clear();
-        self.computed_stats
-            .recalc_after_matches(&self.ranker, self.matches.iter());
+        let combine_groups = self.combine_groups();
+        let snapshot = self.stats_snapshot.as_ref();
+        self.computed_stats = snapshot.map(|s| s.stats.clone()).unwrap_or_default();
+        self.computed_stats.recalc_after_matches(
+            &self.ranker,
+            self.matches
+                .iter()
+                .filter(|m| !snapshot.is_some_and(|s| s.covers(m.id))),
+            combine_groups,
+        );
+    }
+
+    /// prunes the matches expired according to the retention config, at most once per [`RETENTION_INTERVAL`].
+    /// The stats are snapshotted first, so the ratings do not change after restart.
+    #[instrument(skip(self), level = "debug")]
+    async fn apply_retention(&mut self) {
+        if !self.retention_config.is_enabled()
+            || self
+                .last_retention
+                .is_some_and(|t| t.elapsed() < RETENTION_INTERVAL)
+        {
+            return;
+        }
+        self.last_retention = Some(Instant::now());
+        let Some(boundary) = self.snapshot_boundary() else {
+            return;
+        };
+
+        let played_before = self
+            .retention_config
+            .max_age_days
+            .map(|days| Utc::now() - chrono::Duration::days(days.into()));
+        let candidates = self
+            .db
+            .fetch_expired_matches(self.retention_config.max_matches_per_pair, played_before)
+            .await;
+
+        // groups are rated together, so they are pruned only as a whole
+        let kept_groups: HashSet<i64> = candidates
+            .iter()
+            .filter(|m| !m.expired)
+            .filter_map(|m| m.group_id)
+            .collect();
+        // played benchmark matches are found by the stored ones, pruning them would replay them
+        let benchmark_seeds = self.benchmark_seeds();
+        let pruned: Vec<MatchId> = candidates
+            .iter()
+            .filter(|m| m.expired && MatchId::from(m.id) <= boundary)
+            .filter(|m| !benchmark_seeds.contains(&m.seed))
+            .filter(|m| m.group_id.is_none_or(|g| !kept_groups.contains(&g)))
+            .map(|m| MatchId::from(m.id))
+            .collect();
+        if pruned.is_empty() {
+            return;
+        }
+
+        let mut snapshot = self.extended_snapshot(boundary);
+        snapshot.has_pruned = true;
+        self.db.prune_matches(&pruned, &snapshot.to_row()).await;
+        self.match_count -= pruned.len();
+        self.set_snapshot(snapshot);
+        info!("Pruned {} old matches", pruned.len());
+    }
+
+    /// persists the stats of the matches finished since the last snapshot
+    async fn persist_snapshot(&mut self) {
+        let Some(boundary) = self.snapshot_boundary() else {
+            return;
+        };
+        if self
+            .stats_snapshot
+            .as_ref()
+            .is_some_and(|s| s.covers(boundary))
+        {
+            return;
+        }
+        let snapshot = self.extended_snapshot(boundary);
+        self.db.persist_stats_snapshot(&snapshot.to_row()).await;
+        self.set_snapshot(snapshot);
+    }
+
+    /// the covered matches are not needed in memory anymore, they are only replayed from the snapshot
+    fn set_snapshot(&mut self, snapshot: StatsSnapshot) {
+        self.matches.retain(|m| !snapshot.covers(m.id));
+        self.stats_snapshot = Some(snapshot);
+    }
+
+    /// the last match the snapshot can cover: every match before it is finished and rated,
+    /// and no permutation group has matches on both sides of it
+    fn snapshot_boundary(&self) -> Option<MatchId> {
+        let first_pending = self
+            .pending_groups
+            .values()
+            .flat_map(|g| &g.matches)
+            .map(|m| m.id)
+            .min();
+        let mut group_ranges: HashMap<i64, (MatchId, MatchId)> = HashMap::new();
+        for m in &self.matches {
+            if let Some(group_id) = m.group_id {
+                let range = group_ranges.entry(group_id).or_insert((m.id, m.id));
+                *range = (range.0.min(m.id), range.1.max(m.id));
+            }
+        }
+
+        let mut limit = first_pending;
+        loop {
+            let boundary = self
+                .matches
+                .iter()
+                .map(|m| m.id)
+                .filter(|id| limit.is_none_or(|l| *id < l))
+                .max()?;
+            let split_group_start = group_ranges
+                .values()
+                .filter(|(first, last)| *first <= boundary && boundary < *last)
+                .map(|(first, _)| *first)
+                .min();
+            match split_group_start {
+                Some(start) => limit = Some(start),
+                None => return Some(boundary),
+            }
+        }
+    }
+
+    /// current snapshot with the matches up to `last_match_id` replayed in the order of playing,
+    /// the same way as they are replayed at startup
+    fn extended_snapshot(&self, last_match_id: MatchId) -> StatsSnapshot {
+        let snapshot = self.stats_snapshot.as_ref();
+        let mut stats = snapshot.map(|s| s.stats.clone()).unwrap_or_default();
+        let matches = self
+            .matches
+            .iter()
+            .filter(|m| m.id <= last_match_id && !snapshot.is_some_and(|s| s.covers(m.id)))
+            .sorted_by_key(|m| m.id)
+            .collect_vec();
+        stats.recalc_after_matches(&self.ranker, matches.into_iter(), self.combine_groups());
+        StatsSnapshot {
+            last_match_id: snapshot.map_or(last_match_id, |s| s.last_match_id.max(last_match_id)),
+            stats,
+            rating_settings: self.rating_settings.clone(),
+            has_pruned: snapshot.is_some_and(|s| s.has_pruned),
+        }
+    }
+
+    /// random seeds are never repeated, so only the configured ones are counted
+    fn record_seed_failure(&mut self, seed: i64) {
+        let configured = self
+            .matchmaking_config
+            .seeds
+            .as_ref()
+            .is_some_and(|s| s.list().contains(&seed))
+            || self.benchmark_seeds().contains(&seed);
+        if !configured {
+            return;
+        }
+        let failures = self.failed_seeds.entry(seed).or_default();
+        *failures += 1;
+        if *failures == SEED_FAILURE_LIMIT {
+            warn!(
+                "Seed {seed} crashed the referee {SEED_FAILURE_LIMIT} times, \
+                 it is excluded from matchmaking"
+            );
+        }
+    }
+
+    fn is_seed_excluded(&self, seed: i64) -> bool {
+        self.failed_seeds
+            .get(&seed)
+            .is_some_and(|f| *f >= SEED_FAILURE_LIMIT)
+    }
+
+    fn benchmark_seeds(&self) -> HashSet<i64> {
+        self.matchmaking_config
+            .benchmarks
+            .iter()
+            .flat_map(|b| b.seeds.list())
+            .copied()
+            .collect()
     }
 
     fn rating(&self, id: BotId) -> Rating {
@@ -596,6 +1795,43 @@ impl Arena {
             .unwrap_or_default()
     }
 
+    fn head_to_head(&self, id: BotId, opponent_id: BotId) -> WinDrawLoss {
+        self.computed_stats
+            .head_to_head
+            .get(&(id, opponent_id))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn seat_results(&self, id: BotId, index: usize) -> WinDrawLoss {
+        self.computed_stats
+            .seat_results
+            .get(&(id, index))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn score_stats(&self, id: BotId) -> ScoreStats {
+        self.computed_stats
+            .scores
+            .get(&id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn metric_summaries(&self, id: BotId) -> Vec<MetricSummary> {
+        self.computed_stats
+            .metrics
+            .get(&id)
+            .map(|metrics| {
+                metrics
+                    .iter()
+                    .map(|(name, stats)| MetricSummary::new(name.clone(), stats))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     fn matches_with_error(&self, id: BotId) -> usize {
         self.computed_stats
             .matches_with_error
@@ -605,25 +1841,185 @@ impl Arena {
     }
 }
 
-#[derive(Default)]
+/// bots are ordered so that the key does not depend on the seats
+fn benchmark_key(a: BotId, b: BotId, seed: i64) -> (BotId, BotId, i64) {
+    if i64::from(a) < i64::from(b) {
+        (a, b, seed)
+    } else {
+        (b, a, seed)
+    }
+}
+
+/// Stats after all the matches up to `last_match_id`. Such matches are not replayed at startup,
+/// so they can be pruned from the database.
+pub struct StatsSnapshot {
+    last_match_id: MatchId,
+    stats: ComputedStats,
+    /// see [`rating_settings`]
+    rating_settings: String,
+    /// pruned matches can't be replayed, so such snapshot is kept even if the settings change
+    has_pruned: bool,
+}
+
+impl StatsSnapshot {
+    /// `None` if there is no snapshot or it was made with other rating settings and can be
+    /// recalculated from the matches instead
+    pub async fn load(
+        db: &Database,
+        rating_settings: &str,
+    ) -> Result<Option<StatsSnapshot>, anyhow::Error> {
+        let Some(row) = db.fetch_stats_snapshot().await else {
+            return Ok(None);
+        };
+        let stored_settings = row
+            .rating_settings
+            .unwrap_or_else(|| rating_settings.to_string());
+        if stored_settings != rating_settings {
+            if !row.has_pruned {
+                info!("Rating settings changed, ratings are recalculated from scratch");
+                return Ok(None);
+            }
+            warn!("Rating settings changed, ratings of the pruned matches keep the previous ones");
+        }
+        let stats = serde_json::from_str(&row.data).context("Invalid computed stats in db")?;
+        Ok(Some(StatsSnapshot {
+            last_match_id: MatchId::from(row.last_match_id),
+            stats,
+            rating_settings: stored_settings,
+            has_pruned: row.has_pruned,
+        }))
+    }
+
+    pub fn last_match_id(&self) -> MatchId {
+        self.last_match_id
+    }
+
+    pub fn covers(&self, id: MatchId) -> bool {
+        id <= self.last_match_id
+    }
+
+    pub fn ratings(&self) -> &HashMap<BotId, Rating> {
+        &self.stats.ratings
+    }
+
+    fn to_row(&self) -> ComputedStatsRow {
+        ComputedStatsRow {
+            last_match_id: self.last_match_id.into(),
+            data: serde_json::to_string(&self.stats).expect("Cannot serialize computed stats"),
+            rating_settings: Some(self.rating_settings.clone()),
+            has_pruned: self.has_pruned,
+        }
+    }
+}
+
+/// settings the ratings depend on, a snapshot made with other settings is recalculated
+pub fn rating_settings(ranker: &Ranker, permutation_rating: PermutationRating) -> String {
+    serde_json::json!({
+        "ranking": ranker.config(),
+        "permutation_rating": permutation_rating,
+    })
+    .to_string()
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
 struct ComputedStats {
     ratings: HashMap<BotId, Rating>,
     matches_played: HashMap<BotId, usize>,
     matches_with_error: HashMap<BotId, usize>,
+    #[serde(with = "entries")]
+    errors_by_kind: HashMap<(BotId, Option<ErrorKind>), usize>,
+    /// results of the first bot against the second one, stored for both orders
+    #[serde(with = "entries")]
+    head_to_head: HashMap<(BotId, BotId), WinDrawLoss>,
+    /// results of the bot playing at the seat index against the best opponent
+    #[serde(with = "entries")]
+    seat_results: HashMap<(BotId, usize), WinDrawLoss>,
+    scores: HashMap<BotId, ScoreStats>,
+    metrics: HashMap<BotId, BTreeMap<String, MetricStats>>,
+}
+
+/// bounded aggregate of the reported values of a custom metric
+#[derive(Clone, Serialize, Deserialize)]
+struct MetricStats {
+    count: usize,
+    sum: f64,
+    min: f64,
+    max: f64,
+    /// uniform sample of the values for the percentiles (reservoir sampling)
+    sample: Vec<f64>,
+}
+
+impl MetricStats {
+    fn new(value: f64) -> Self {
+        MetricStats {
+            count: 1,
+            sum: value,
+            min: value,
+            max: value,
+            sample: vec![value],
+        }
+    }
+
+    fn record(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        if self.sample.len() < METRIC_SAMPLE_SIZE {
+            self.sample.push(value);
+        } else {
+            // every value so far stays in the sample with the same probability
+            let index = thread_rng().gen_range(0..self.count);
+            if index < METRIC_SAMPLE_SIZE {
+                self.sample[index] = value;
+            }
+        }
+    }
+}
+
+/// scores are assumed to be better when higher
+#[derive(Default, Clone, Copy, Serialize, Deserialize)]
+struct ScoreStats {
+    matches: usize,
+    score_sum: f64,
+    margin_sum: f64,
+}
+
+impl ScoreStats {
+    fn average_score(&self) -> Option<f64> {
+        (self.matches > 0).then(|| self.score_sum / self.matches as f64)
+    }
+
+    fn average_margin(&self) -> Option<f64> {
+        (self.matches > 0).then(|| self.margin_sum / self.matches as f64)
+    }
 }
 
 impl ComputedStats {
-    pub fn clear(&mut self) {
-        *self = Default::default();
+    /// the bot's own stats are removed, its opponents keep the ratings gained against it
+    fn remove_bot(&mut self, id: BotId) {
+        self.ratings.remove(&id);
+        self.matches_played.remove(&id);
+        self.matches_with_error.remove(&id);
+        self.errors_by_kind.retain(|(bot_id, _), _| *bot_id != id);
+        self.head_to_head.retain(|(a, b), _| *a != id && *b != id);
+        self.seat_results.retain(|(bot_id, _), _| *bot_id != id);
+        self.scores.remove(&id);
+        self.metrics.remove(&id);
     }
 
     pub fn recalc_after_matches<'a>(
         &mut self,
         ranker: &Ranker,
         matches: impl Iterator<Item = &'a Match> + Clone,
+        combine_groups: bool,
     ) {
         // rating
-        ranker.recalc_rating(&mut self.ratings, matches.clone());
+        if combine_groups {
+            self.recalc_ratings_combining_groups(ranker, matches.clone());
+        } else {
+            ranker.recalc_rating(&mut self.ratings, matches.clone());
+        }
 
         // matches_played and matches_with_error
         for m in matches.clone() {
@@ -638,8 +2034,299 @@ impl ComputedStats {
                         .entry(p.bot_id)
                         .and_modify(|w| *w += 1)
                         .or_insert(1);
+                    *self
+                        .errors_by_kind
+                        .entry((p.bot_id, p.error_kind))
+                        .or_default() += 1;
+                }
+            }
+
+            // seat_results
+            for (index, p) in m.participants.iter().enumerate() {
+                let best_opponent_rank = m
+                    .participants
+                    .iter()
+                    .filter(|o| !p.is_teammate(o))
+                    .map(|o| o.rank)
+                    .min();
+                if let Some(best_opponent_rank) = best_opponent_rank {
+                    self.seat_results
+                        .entry((p.bot_id, index))
+                        .or_default()
+                        .record(p.rank.cmp(&best_opponent_rank));
+                }
+            }
+
+            // scores
+            for p in &m.participants {
+                let Some(score) = p.score else {
+                    continue;
+                };
+                let best_opponent_score = m
+                    .participants
+                    .iter()
+                    .filter(|o| !p.is_teammate(o))
+                    .filter_map(|o| o.score)
+                    .max_by(f64::total_cmp);
+                if let Some(best_opponent_score) = best_opponent_score {
+                    let stats = self.scores.entry(p.bot_id).or_default();
+                    stats.matches += 1;
+                    stats.score_sum += score;
+                    stats.margin_sum += score - best_opponent_score;
+                }
+            }
+
+            // metrics
+            for p in &m.participants {
+                for (name, value) in &p.metrics {
+                    self.metrics
+                        .entry(p.bot_id)
+                        .or_default()
+                        .entry(name.clone())
+                        .and_modify(|stats| stats.record(*value))
+                        .or_insert_with(|| MetricStats::new(*value));
                 }
             }
+
+            // head_to_head, teammates do not play against each other
+            for (a, b) in m
+                .participants
+                .iter()
+                .tuple_combinations()
+                .filter(|(a, b)| !a.is_teammate(b))
+            {
+                self.head_to_head
+                    .entry((a.bot_id, b.bot_id))
+                    .or_default()
+                    .record(a.rank.cmp(&b.rank));
+                self.head_to_head
+                    .entry((b.bot_id, a.bot_id))
+                    .or_default()
+                    .record(b.rank.cmp(&a.rank));
+            }
         }
     }
+
+    /// every group is rated as a single match at the position of its last match
+    fn recalc_ratings_combining_groups<'a>(
+        &mut self,
+        ranker: &Ranker,
+        matches: impl Iterator<Item = &'a Match> + Clone,
+    ) {
+        let mut last_index = HashMap::new();
+        for (index, m) in matches.clone().enumerate() {
+            if let Some(group_id) = m.group_id {
+                last_index.insert(group_id, index);
+            }
+        }
+
+        let mut groups: HashMap<i64, Vec<&Match>> = HashMap::new();
+        for (index, m) in matches.enumerate() {
+            let Some(group_id) = m.group_id else {
+                ranker.recalc_rating(&mut self.ratings, std::iter::once(m));
+                continue;
+            };
+            let group = groups.entry(group_id).or_default();
+            group.push(m);
+            if last_index[&group_id] == index {
+                ranker.recalc_rating_combined(&mut self.ratings, group);
+                groups.remove(&group_id);
+            }
+        }
+    }
+}
+
+/// maps with tuple keys are stored as lists of entries, JSON keys can only be strings
+mod entries {
+    use super::*;
+
+    pub fn serialize<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let entries = Vec::<(K, V)>::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{EmbeddedWorkerConfig, RankingConfig};
+    use crate::domain::{BotName, Language, Participant, SourceCode};
+    use std::path::Path;
+    use tokio::sync::Semaphore;
+
+    const MATCHMAKING_CONFIG: &str = "min_matches = 10\nmin_matches_preference = 1.0";
+
+    async fn create_arena(path: &Path, game_config: &str, matchmaking_config: &str) -> Arena {
+        let token = CancellationToken::new();
+        let worker_config = EmbeddedWorkerConfig {
+            threads: 1,
+            cmd_play_match: "true".to_string(),
+            cmd_build: "true".to_string(),
+            cmd_run: "true".to_string(),
+        };
+        let worker = EmbeddedWorker::new(
+            path,
+            worker_config,
+            Arc::new(Semaphore::new(1)),
+            Default::default(),
+            token.clone(),
+        );
+        Arena::new(
+            toml::from_str(game_config).unwrap(),
+            toml::from_str(matchmaking_config).unwrap(),
+            RetentionConfig::default(),
+            Ranker::new(RankingConfig::OpenSkill),
+            Database::connect(path).await,
+            worker,
+            Notifier::start(vec![], token),
+            Default::default(),
+        )
+    }
+
+    async fn create_bot(db: &Database, name: &str) -> BotId {
+        let mut bot = Bot::new(
+            BotName::try_from(name.to_string()).unwrap(),
+            SourceCode::try_from(format!("source of {name}")).unwrap(),
+            Language::try_from("python".to_string()).unwrap(),
+            None,
+        );
+        db.persist_bot(&mut bot).await;
+        bot.id
+    }
+
+    fn group_match(a: BotId, b: BotId, group_id: i64) -> Match {
+        let mut m = Match::new(
+            1,
+            vec![Participant::ranked(a, 0, 1), Participant::ranked(b, 1, 2)],
+        );
+        m.group_id = Some(group_id);
+        m
+    }
+
+    #[tokio::test]
+    async fn pending_group_is_rated_once_after_bot_deletion() {
+        let dir = tempfile::tempdir().unwrap();
+        let game_config = indoc::indoc! {r#"
+            min_players = 2
+            max_players = 2
+            symmetric = false
+            permutation_rating = "sum_ranks"
+        "#};
+        let mut arena = create_arena(dir.path(), game_config, MATCHMAKING_CONFIG).await;
+        let a = create_bot(&arena.db, "a").await;
+        let b = create_bot(&arena.db, "b").await;
+        let c = create_bot(&arena.db, "c").await;
+        arena.load_from_db().await;
+
+        // the deleted bot has a rated match, so the snapshot covering it is dropped
+        let mut rated = [Match::new(
+            1,
+            vec![Participant::ranked(a, 0, 1), Participant::ranked(b, 1, 2)],
+        )];
+        arena.db.persist_matches(&mut rated).await;
+        arena.matches.extend(rated);
+        arena.rate_new_matches(0);
+        arena.persist_snapshot().await;
+
+        let mut first = [group_match(b, c, 1)];
+        arena.db.persist_matches(&mut first).await;
+        arena.pending_groups.insert(
+            1,
+            PendingGroup {
+                remaining: 1,
+                matches: first.into_iter().collect(),
+            },
+        );
+
+        arena.cmd_delete_bot(a).await;
+
+        let mut second = [group_match(c, b, 1)];
+        arena.db.persist_matches(&mut second).await;
+        let group = arena.pending_groups.get_mut(&1).unwrap();
+        group.matches.extend(second);
+        group.remaining = 0;
+        arena.finish_group(1);
+
+        let mut expected = ComputedStats::default();
+        let matches = arena.db.fetch_matches(None).await;
+        expected.recalc_after_matches(&arena.ranker, matches.iter(), true);
+        for id in [b, c] {
+            assert_eq!(arena.rating(id).mu, expected.ratings[&id].mu);
+            assert_eq!(arena.matches_played(id), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn configured_seed_is_excluded_after_repeated_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let game_config = "min_players = 2\nmax_players = 2\nsymmetric = true";
+        let matchmaking_config = format!("{MATCHMAKING_CONFIG}\nseeds = [1, 2]");
+        let mut arena = create_arena(dir.path(), game_config, &matchmaking_config).await;
+
+        for _ in 1..SEED_FAILURE_LIMIT {
+            arena.record_seed_failure(1);
+        }
+        assert!(!arena.is_seed_excluded(1));
+        arena.record_seed_failure(1);
+        assert!(arena.is_seed_excluded(1));
+        assert_eq!(arena.random_seed(&mut thread_rng()), Some(2));
+
+        // random seeds are not repeated, so they are not tracked
+        arena.record_seed_failure(7);
+        assert!(!arena.failed_seeds.contains_key(&7));
+    }
+
+    #[tokio::test]
+    async fn failing_bot_is_paused_until_unarchived() {
+        let dir = tempfile::tempdir().unwrap();
+        let game_config = "min_players = 2\nmax_players = 2\nsymmetric = true";
+        let matchmaking_config =
+            format!("{MATCHMAKING_CONFIG}\nmax_error_rate = 0.5\nerror_check_matches = 2");
+        let mut arena = create_arena(dir.path(), game_config, &matchmaking_config).await;
+        let a = create_bot(&arena.db, "a").await;
+        let b = create_bot(&arena.db, "b").await;
+        arena.load_from_db().await;
+
+        for seed in 0..2 {
+            let mut m = Match::new(
+                seed,
+                vec![Participant::ranked(a, 0, 2), Participant::ranked(b, 1, 1)],
+            );
+            m.participants[0].error = true;
+            arena.matches.push(m);
+        }
+        arena.rate_new_matches(0);
+        arena.pause_failing_bots(&HashMap::new()).await;
+
+        let bots = arena.db.fetch_bots().await;
+        let paused = bots.iter().find(|bot| bot.id == a).unwrap();
+        assert!(!paused.archived);
+        assert_eq!(
+            paused.paused_reason.as_deref(),
+            Some("failed in 2 of 2 matches")
+        );
+        assert!(bots.iter().find(|bot| bot.id == b).unwrap().is_matchmade());
+
+        arena.cmd_archive_bot(a, false).await;
+        assert!(arena
+            .bots
+            .iter()
+            .find(|bot| bot.id == a)
+            .unwrap()
+            .is_matchmade());
+    }
 }
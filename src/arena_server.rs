@@ -1,16 +1,27 @@
-use crate::config::{Config, WorkerConfig};
+use crate::api::GameHandle;
+use crate::config::{Config, WorkerConfig, TLS_CERT_FILE_NAME, TLS_KEY_FILE_NAME};
 use crate::db::Database;
 use crate::embedded_worker::EmbeddedWorker;
+use crate::metrics::GameMetrics;
+use crate::notifier::Notifier;
 use crate::ranking::Ranker;
 use crate::{api, arena};
-use std::fs::OpenOptions;
+use anyhow::bail;
+use axum_server::tls_rustls::RustlsConfig;
+use std::fs::{File, OpenOptions, TryLockError};
 use std::net::SocketAddr;
 use std::path::Path;
 use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
 use tracing::{warn, Level};
 use tracing_subscriber::fmt::format::FmtSpan;
 
+/// held by the running arena, so commands rewriting the database can refuse to run meanwhile
+const LOCK_FILE_NAME: &str = "cgarena.lock";
+
 pub async fn start(arena_path: &Path) {
     let config = Config::load(arena_path).expect("Cannot load arena config");
 
@@ -19,6 +30,14 @@ pub async fn start(arena_path: &Path) {
         return;
     }
 
+    let _lock = match lock(arena_path) {
+        Ok(lock) => lock,
+        Err(e) => {
+            eprintln!("Cannot start the arena: {e:#}");
+            return;
+        }
+    };
+
     let log_file = OpenOptions::new()
         .append(true)
         .create(true)
@@ -38,26 +57,53 @@ pub async fn start(arena_path: &Path) {
         .with_span_events(FmtSpan::CLOSE)
         .init();
 
-    let db = Database::connect(arena_path).await;
-    let ranker = Ranker::new(config.ranking);
     let token = CancellationToken::new();
+    // matches of all the games share the same pool of threads
+    let pool = Arc::new(Semaphore::new(config.threads));
+    let notifier = Notifier::start(config.notifications, token.clone());
+    let mut arena_tasks = JoinSet::new();
+    let mut games = Vec::with_capacity(config.games.len());
 
-    let [WorkerConfig::Embedded(cfg)] = config.workers.as_slice() else {
-        panic!("In the current version only single embedded worker supported");
-    };
-    let worker = EmbeddedWorker::new(arena_path, cfg.clone(), token.clone());
+    for game in config.games {
+        let game_path = arena_path.join(&game.dir);
+        std::fs::create_dir_all(&game_path).expect("Cannot create game directory");
 
-    let (arena_tx, arena_rx) = tokio::sync::mpsc::channel(16);
+        let db = Database::connect(&game_path).await;
+        let ranker = Ranker::new(game.ranking);
 
-    let arena_task_handle = tokio::spawn(arena::run(
-        config.game,
-        config.matchmaking,
-        ranker,
-        db,
-        worker,
-        arena_rx,
-        token.clone(),
-    ));
+        let [WorkerConfig::Embedded(cfg)] = game.workers.as_slice() else {
+            panic!("In the current version only single embedded worker supported");
+        };
+        let metrics = Arc::new(GameMetrics::default());
+        let worker = EmbeddedWorker::new(
+            &game_path,
+            cfg.clone(),
+            Arc::clone(&pool),
+            Arc::clone(&metrics),
+            token.clone(),
+        );
+
+        let (arena_tx, arena_rx) = tokio::sync::mpsc::channel(16);
+
+        arena_tasks.spawn(arena::run(
+            game.game,
+            game.matchmaking,
+            game.retention,
+            ranker,
+            db.clone(),
+            worker,
+            notifier.for_game(&game.name),
+            Arc::clone(&metrics),
+            arena_rx,
+            token.clone(),
+        ));
+        games.push(GameHandle {
+            name: game.name,
+            arena_tx,
+            db,
+            metrics,
+        });
+    }
 
     let exposed = config.server.expose;
     let addr = if exposed {
@@ -74,13 +120,39 @@ pub async fn start(arena_path: &Path) {
         .local_addr()
         .expect("Cannot get local address of tcp binding");
 
-    let api_task_handle = tokio::spawn(api::start(listener, arena_tx, token.clone()));
+    let tls = match (&config.server.tls_cert, &config.server.tls_key) {
+        (Some(cert), Some(key)) => {
+            let _ = rustls::crypto::ring::default_provider().install_default();
+            match RustlsConfig::from_pem_file(arena_path.join(cert), arena_path.join(key)).await {
+                Ok(tls) => Some(tls),
+                Err(e) => {
+                    eprintln!("Cannot load TLS certificate: {e}");
+                    return;
+                }
+            }
+        }
+        _ => None,
+    };
+    let scheme = if tls.is_some() { "https" } else { "http" };
+
+    if exposed && config.server.users.is_empty() {
+        warn!("Arena is exposed to the network without authentication, configure 'server.users'");
+    }
+
+    let api_task_handle = tokio::spawn(api::start(
+        listener,
+        games,
+        config.threads,
+        config.server.users,
+        tls,
+        token.clone(),
+    ));
 
     println!("CG Arena started, press Ctrl+C to stop it");
-    println!("Local:   http://localhost:{}/", bind_addr.port());
+    println!("Local:   {scheme}://localhost:{}/", bind_addr.port());
     if exposed {
         if let Ok(ip) = local_ip_address::local_ip() {
-            println!("Network: http://{}:{}/", ip, bind_addr.port());
+            println!("Network: {scheme}://{}:{}/", ip, bind_addr.port());
         }
     } else {
         println!("Network: use 'server.expose' config param to expose",);
@@ -90,7 +162,7 @@ pub async fn start(arena_path: &Path) {
         _ = shutdown_signal() => {
             token.cancel();
         },
-        _ = arena_task_handle => {
+        _ = arena_tasks.join_next() => {
             warn!("Arena task terminated unexpectedly.");
         }
         _ = api_task_handle => {
@@ -101,16 +173,46 @@ pub async fn start(arena_path: &Path) {
     println!("Stopping CG Arena... press Ctrl+C again to kill it");
 }
 
-pub fn init(path: &Path) {
+/// exclusive lock of the arena directory, released when the file is dropped or the process exits
+pub fn lock(arena_path: &Path) -> Result<File, anyhow::Error> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(arena_path.join(LOCK_FILE_NAME))?;
+    match file.try_lock() {
+        Ok(()) => Ok(file),
+        Err(TryLockError::WouldBlock) => bail!("the arena is already running"),
+        Err(TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+pub fn init(path: &Path, tls: bool) {
     match std::fs::create_dir(path) {
         Ok(_) => (),
         Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => (),
         Err(e) => panic!("Cannot create new arena: {}", e),
     }
-    Config::create_default(path);
+    Config::create_default(path, tls);
+    if tls {
+        generate_self_signed_cert(path);
+    }
     println!("New arena has been initialized in {}", path.display());
 }
 
+/// browsers warn about self-signed certificates, replace the files to use a trusted one
+fn generate_self_signed_cert(path: &Path) {
+    let mut names = vec!["localhost".to_string(), "127.0.0.1".to_string()];
+    if let Ok(ip) = local_ip_address::local_ip() {
+        names.push(ip.to_string());
+    }
+    let cert = rcgen::generate_simple_self_signed(names).expect("Cannot generate certificate");
+    std::fs::write(path.join(TLS_CERT_FILE_NAME), cert.cert.pem())
+        .expect("Cannot write certificate");
+    std::fs::write(path.join(TLS_KEY_FILE_NAME), cert.key_pair.serialize_pem())
+        .expect("Cannot write certificate key");
+}
+
 async fn shutdown_signal() {
     let ctrl_c = async {
         tokio::signal::ctrl_c()
@@ -120,7 +222,7 @@ async fn shutdown_signal() {
 
     #[cfg(unix)]
     let terminate = async {
-        tokio::signal::unix::signal(signal::unix::SignalKind::terminate())
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
             .expect("failed to install signal handler")
             .recv()
             .await;
@@ -143,7 +245,7 @@ mod test {
     fn new_arena_can_be_created_in_new_folder() {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("test");
-        init(&path);
+        init(&path, false);
         assert!(path.join("cgarena_config.toml").exists());
     }
 
@@ -152,7 +254,27 @@ mod test {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("test");
         std::fs::create_dir(&path).unwrap();
-        init(&path);
+        init(&path, false);
         assert!(path.join("cgarena_config.toml").exists());
     }
+
+    #[test]
+    fn running_arena_is_locked() {
+        let dir = tempfile::tempdir().unwrap();
+        let running = lock(dir.path()).unwrap();
+        assert!(lock(dir.path()).is_err());
+        drop(running);
+        assert!(lock(dir.path()).is_ok());
+    }
+
+    #[test]
+    fn new_arena_can_be_created_with_tls() {
+        let dir = tempfile::tempdir().unwrap();
+        init(dir.path(), true);
+        let config = Config::load(dir.path()).unwrap();
+        config.validate().unwrap();
+        assert!(config.server.tls_cert.is_some());
+        assert!(dir.path().join(TLS_CERT_FILE_NAME).exists());
+        assert!(dir.path().join(TLS_KEY_FILE_NAME).exists());
+    }
 }
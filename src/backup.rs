@@ -0,0 +1,465 @@
+use crate::arena_server;
+use crate::config::Config;
+use crate::db::Database;
+use crate::domain::{
+    Bot, BotId, Build, BuildResult, BuildStatus, ErrorKind, Match, MatchId, Participant, WorkerName,
+};
+use anyhow::{bail, Context};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// bumped on incompatible changes of [`ArenaExport`]
+const FORMAT_VERSION: u32 = 1;
+/// name of the JSON file inside `.tar.gz` exports
+const ARCHIVE_ENTRY_NAME: &str = "arena.json";
+
+/// Everything stored in the database of a single game.
+#[derive(Serialize, Deserialize)]
+struct ArenaExport {
+    version: u32,
+    bots: Vec<ExportedBot>,
+    builds: Vec<ExportedBuild>,
+    matches: Vec<ExportedMatch>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedBot {
+    id: i64,
+    name: String,
+    source_code: String,
+    language: String,
+    created_at: DateTime<Utc>,
+    archived: bool,
+    author: Option<String>,
+    #[serde(default)]
+    paused_reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedBuild {
+    bot_id: i64,
+    worker_name: String,
+    status: ExportedBuildStatus,
+    stderr: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum ExportedBuildStatus {
+    Pending,
+    Running,
+    Success,
+    Failure,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedMatch {
+    id: i64,
+    seed: i64,
+    group_id: Option<i64>,
+    params: BTreeMap<String, String>,
+    participants: Vec<ExportedParticipant>,
+    /// missing in the exports made before it was added, such matches are imported as new
+    #[serde(default)]
+    created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedParticipant {
+    bot_id: i64,
+    rank: u8,
+    error: bool,
+    error_kind: Option<String>,
+    error_message: Option<String>,
+    score: Option<f64>,
+    metrics: BTreeMap<String, f64>,
+    team: u8,
+}
+
+pub struct ImportSummary {
+    pub bots_created: usize,
+    /// bots with the same name and source code which already were in the arena
+    pub bots_existing: usize,
+    pub matches_imported: usize,
+    /// matches between the already existing bots
+    pub matches_skipped: usize,
+}
+
+/// Writes the database of the game to `out`, `.tar.gz` files are compressed, otherwise plain JSON.
+pub async fn export(
+    arena_path: &Path,
+    out: &Path,
+    game: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    let db = connect(arena_path, game).await?;
+    let bots = db.fetch_bots().await;
+    let builds = db.fetch_builds().await;
+    let matches = db.fetch_matches(None).await;
+
+    let export = ArenaExport {
+        version: FORMAT_VERSION,
+        bots: bots.iter().map(ExportedBot::from).collect(),
+        builds: builds.iter().map(ExportedBuild::from).collect(),
+        matches: matches.iter().map(ExportedMatch::from).collect(),
+    };
+    let json = serde_json::to_vec(&export)?;
+
+    if is_archive(out) {
+        let file = File::create(out).with_context(|| format!("Cannot create {}", out.display()))?;
+        let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(json.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(Utc::now().timestamp() as u64);
+        archive.append_data(&mut header, ARCHIVE_ENTRY_NAME, json.as_slice())?;
+        archive.into_inner()?.finish()?;
+    } else {
+        std::fs::write(out, &json).with_context(|| format!("Cannot write {}", out.display()))?;
+    }
+
+    println!(
+        "Exported {} bots and {} matches to {}",
+        export.bots.len(),
+        export.matches.len(),
+        out.display()
+    );
+    Ok(())
+}
+
+/// Merges the exported bots and matches into the game database, the arena should not be running.
+/// Bots are matched by name, a bot with the same name but different source code is a conflict
+/// and nothing is imported then.
+pub async fn import(
+    arena_path: &Path,
+    file: &Path,
+    game: Option<&str>,
+) -> Result<ImportSummary, anyhow::Error> {
+    let _lock = arena_server::lock(arena_path).context("Stop the arena before import")?;
+    let export = read_export(file)?;
+    if export.version != FORMAT_VERSION {
+        bail!(
+            "Unsupported export version {}, expected {FORMAT_VERSION}",
+            export.version
+        );
+    }
+
+    let db = connect(arena_path, game).await?;
+    let existing_bots = db.fetch_bots().await;
+
+    // validate everything before writing anything
+    let mut new_bots = Vec::new();
+    let mut bot_ids: HashMap<i64, BotId> = HashMap::new();
+    for exported in &export.bots {
+        let bot =
+            Bot::try_from(exported).with_context(|| format!("Invalid bot '{}'", exported.name))?;
+        match existing_bots.iter().find(|b| b.name == bot.name) {
+            Some(existing) if *existing.source_code == *bot.source_code => {
+                bot_ids.insert(exported.id, existing.id);
+            }
+            Some(_) => bail!(
+                "Bot '{}' already exists with a different source code, rename one of them",
+                exported.name
+            ),
+            None => new_bots.push((exported.id, bot)),
+        }
+    }
+    let mut matches = Vec::with_capacity(export.matches.len());
+    for exported in &export.matches {
+        let m =
+            Match::try_from(exported).with_context(|| format!("Invalid match {}", exported.id))?;
+        if let Some(p) = m
+            .participants
+            .iter()
+            .find(|p| !export.bots.iter().any(|b| BotId::from(b.id) == p.bot_id))
+        {
+            bail!(
+                "Match {} refers to unknown bot {}",
+                exported.id,
+                i64::from(p.bot_id)
+            );
+        }
+        matches.push(m);
+    }
+    let builds = export
+        .builds
+        .iter()
+        .map(|b| Ok((b, WorkerName::try_from(b.worker_name.clone())?)))
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+    let bots_existing = bot_ids.len();
+    let bots_created = new_bots.len();
+    for (exported_id, mut bot) in new_bots {
+        db.persist_bot(&mut bot).await;
+        bot_ids.insert(exported_id, bot.id);
+    }
+
+    // executables are not exported, so successful builds are redone
+    for (exported, worker_name) in builds {
+        let Some(bot_id) = bot_ids.get(&exported.bot_id).copied() else {
+            continue;
+        };
+        if existing_bots.iter().any(|b| b.id == bot_id) {
+            continue;
+        }
+        let mut build = Build::new(bot_id, worker_name);
+        if let (ExportedBuildStatus::Failure, Some(stderr)) = (exported.status, &exported.stderr) {
+            build.status = BuildStatus::Finished(BuildResult::Failure {
+                stderr: stderr.clone(),
+            });
+        }
+        db.persist_build(&build).await;
+    }
+
+    let group_offset = db.fetch_max_group_id().await.unwrap_or(0);
+    let mut imported = Vec::with_capacity(matches.len());
+    let mut matches_skipped = 0;
+    for mut m in matches {
+        let all_existed = m.participants.iter().all(|p| {
+            existing_bots
+                .iter()
+                .any(|b| b.id == bot_ids[&i64::from(p.bot_id)])
+        });
+        if all_existed {
+            matches_skipped += 1;
+            continue;
+        }
+        for p in &mut m.participants {
+            p.bot_id = bot_ids[&i64::from(p.bot_id)];
+        }
+        m.group_id = m.group_id.map(|g| g + group_offset);
+        imported.push(m);
+    }
+    db.persist_matches(&mut imported).await;
+    let matches_imported = imported.len();
+
+    Ok(ImportSummary {
+        bots_created,
+        bots_existing,
+        matches_imported,
+        matches_skipped,
+    })
+}
+
+/// shrinks the database file of the game after old matches were pruned, returns its sizes before and after.
+/// The arena must be stopped, its writes would fail while the database is rebuilt.
+pub async fn vacuum(arena_path: &Path, game: Option<&str>) -> Result<(u64, u64), anyhow::Error> {
+    let _lock = arena_server::lock(arena_path).context("Stop the arena before vacuum")?;
+    let db = connect(arena_path, game).await?;
+    db.vacuum().await
+}
+
+async fn connect(arena_path: &Path, game: Option<&str>) -> Result<Database, anyhow::Error> {
+    let config = Config::load(arena_path).context("Cannot load arena config")?;
+    config.validate().context("Invalid config")?;
+    let game = config.find_game(game)?;
+    let game_path = arena_path.join(&game.dir);
+    if !game_path.exists() {
+        bail!("Game directory {} does not exist", game_path.display());
+    }
+    Ok(Database::connect(&game_path).await)
+}
+
+fn is_archive(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+    name.ends_with(".tar.gz") || name.ends_with(".tgz")
+}
+
+fn read_export(path: &Path) -> Result<ArenaExport, anyhow::Error> {
+    let file = File::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+    if !is_archive(path) {
+        return Ok(serde_json::from_reader(BufReader::new(file))?);
+    }
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.path()?.as_os_str() == ARCHIVE_ENTRY_NAME {
+            let mut json = Vec::new();
+            entry.read_to_end(&mut json)?;
+            return Ok(serde_json::from_slice(&json)?);
+        }
+    }
+    bail!("{ARCHIVE_ENTRY_NAME} is not found in {}", path.display())
+}
+
+impl From<&Bot> for ExportedBot {
+    fn from(bot: &Bot) -> Self {
+        ExportedBot {
+            id: bot.id.into(),
+            name: bot.name.to_string(),
+            source_code: bot.source_code.to_string(),
+            language: bot.language.to_string(),
+            created_at: bot.created_at,
+            archived: bot.archived,
+            author: bot.author.clone(),
+            paused_reason: bot.paused_reason.clone(),
+        }
+    }
+}
+
+impl TryFrom<&ExportedBot> for Bot {
+    type Error = anyhow::Error;
+
+    fn try_from(bot: &ExportedBot) -> Result<Self, Self::Error> {
+        Ok(Bot {
+            id: BotId::UNINITIALIZED,
+            name: bot.name.clone().try_into()?,
+            source_code: bot.source_code.clone().try_into()?,
+            language: bot.language.clone().try_into()?,
+            created_at: bot.created_at,
+            archived: bot.archived,
+            paused_reason: bot.paused_reason.clone(),
+            author: bot.author.clone(),
+        })
+    }
+}
+
+impl From<&Build> for ExportedBuild {
+    fn from(build: &Build) -> Self {
+        let (status, stderr) = match &build.status {
+            BuildStatus::Pending => (ExportedBuildStatus::Pending, None),
+            BuildStatus::Running => (ExportedBuildStatus::Running, None),
+            BuildStatus::Finished(BuildResult::Success) => (ExportedBuildStatus::Success, None),
+            BuildStatus::Finished(BuildResult::Failure { stderr }) => {
+                (ExportedBuildStatus::Failure, Some(stderr.clone()))
+            }
+        };
+        ExportedBuild {
+            bot_id: build.bot_id.into(),
+            worker_name: build.worker_name.to_string(),
+            status,
+            stderr,
+        }
+    }
+}
+
+impl From<&Match> for ExportedMatch {
+    fn from(m: &Match) -> Self {
+        ExportedMatch {
+            id: m.id.into(),
+            seed: m.seed,
+            group_id: m.group_id,
+            params: m.params.clone(),
+            created_at: Some(m.created_at),
+            participants: m
+                .participants
+                .iter()
+                .map(|p| ExportedParticipant {
+                    bot_id: p.bot_id.into(),
+                    rank: p.rank,
+                    error: p.error,
+                    error_kind: p.error_kind.map(|k| k.as_str().to_string()),
+                    error_message: p.error_message.clone(),
+                    score: p.score,
+                    metrics: p.metrics.clone(),
+                    team: p.team,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<&ExportedMatch> for Match {
+    type Error = anyhow::Error;
+
+    fn try_from(m: &ExportedMatch) -> Result<Self, Self::Error> {
+        let participants = m
+            .participants
+            .iter()
+            .map(|p| {
+                Ok(Participant {
+                    bot_id: p.bot_id.into(),
+                    rank: p.rank,
+                    error: p.error,
+                    error_kind: p
+                        .error_kind
+                        .as_deref()
+                        .map(ErrorKind::try_from)
+                        .transpose()?,
+                    error_message: p.error_message.clone(),
+                    score: p.score,
+                    metrics: p.metrics.clone(),
+                    team: p.team,
+                })
+            })
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+        Ok(Match {
+            id: MatchId::UNINITIALIZED,
+            seed: m.seed,
+            participants,
+            group_id: m.group_id,
+            params: m.params.clone(),
+            created_at: m.created_at.unwrap_or_else(Utc::now),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::{BotName, Language, SourceCode};
+
+    async fn create_arena(path: &Path, bots: &[&str]) -> Vec<BotId> {
+        crate::arena_server::init(path, false);
+        let db = Database::connect(path).await;
+        let mut ids = Vec::new();
+        for name in bots {
+            let mut bot = Bot::new(
+                BotName::try_from(name.to_string()).unwrap(),
+                SourceCode::try_from(format!("source of {name}")).unwrap(),
+                Language::try_from("python".to_string()).unwrap(),
+                None,
+            );
+            db.persist_bot(&mut bot).await;
+            ids.push(bot.id);
+        }
+        ids
+    }
+
+    #[tokio::test]
+    async fn arenas_can_be_merged() {
+        let dir = tempfile::tempdir().unwrap();
+        let (source, target) = (dir.path().join("source"), dir.path().join("target"));
+        let ids = create_arena(&source, &["a", "b"]).await;
+        let db = Database::connect(&source).await;
+        let mut m = Match::new(
+            7,
+            vec![
+                Participant::ranked(ids[0], 0, 0),
+                Participant::ranked(ids[1], 1, 1),
+            ],
+        );
+        m.created_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        db.persist_matches(std::slice::from_mut(&mut m)).await;
+        create_arena(&target, &["a", "c"]).await;
+
+        let out = dir.path().join("arena.tar.gz");
+        export(&source, &out, None).await.unwrap();
+        let lock = crate::arena_server::lock(&target).unwrap();
+        assert!(import(&target, &out, None).await.is_err());
+        drop(lock);
+        let summary = import(&target, &out, None).await.unwrap();
+        assert_eq!(summary.bots_created, 1);
+        assert_eq!(summary.bots_existing, 1);
+        assert_eq!(summary.matches_imported, 1);
+
+        // everything is present already
+        let summary = import(&target, &out, None).await.unwrap();
+        assert_eq!(summary.bots_created, 0);
+        assert_eq!(summary.matches_imported, 0);
+        assert_eq!(summary.matches_skipped, 1);
+
+        let db = Database::connect(&target).await;
+        assert_eq!(db.fetch_bots().await.len(), 3);
+        let matches = db.fetch_matches(None).await;
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].seed, 7);
+        assert_eq!(matches[0].created_at, m.created_at);
+    }
+}
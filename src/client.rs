@@ -0,0 +1,177 @@
+use crate::api::auth::API_TOKEN_HEADER;
+use crate::arena::{BotMinimal, CreateBotResult};
+use crate::config::Config;
+use crate::domain::{BotId, BotName, Language, SourceCode};
+use anyhow::{bail, Context};
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Certificate, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+/// HTTP client for the API of a running arena.
+pub struct ArenaClient {
+    http: reqwest::Client,
+    /// URL of the API of the target game
+    api_url: String,
+}
+
+#[derive(Serialize)]
+struct CreateBotRequest<'a> {
+    name: &'a str,
+    source_code: &'a str,
+    language: &'a str,
+}
+
+#[derive(Deserialize)]
+struct BotMinimalResponse {
+    id: i64,
+    name: String,
+    archived: bool,
+    /// missing in the responses of older arenas
+    #[serde(default)]
+    paused_reason: Option<String>,
+    author: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error_code: String,
+    message: String,
+}
+
+impl TryFrom<BotMinimalResponse> for BotMinimal {
+    type Error = anyhow::Error;
+
+    fn try_from(value: BotMinimalResponse) -> Result<Self, Self::Error> {
+        Ok(BotMinimal {
+            id: value.id.into(),
+            name: value.name.try_into()?,
+            archived: value.archived,
+            paused_reason: value.paused_reason,
+            author: value.author,
+        })
+    }
+}
+
+impl ArenaClient {
+    /// default game of the arena is used if `game` is not set,
+    /// `token` is required when the arena has users configured,
+    /// `tls_cert` is trusted in addition to the system ones, e.g. a self-signed certificate of the arena
+    pub fn new(
+        base_url: &str,
+        game: Option<&str>,
+        token: Option<&str>,
+        tls_cert: Option<&Path>,
+    ) -> Result<Self, anyhow::Error> {
+        let base_url = base_url.trim_end_matches('/');
+        let api_url = match game {
+            Some(game) => format!("{base_url}/api/games/{game}"),
+            None => format!("{base_url}/api"),
+        };
+        let mut headers = HeaderMap::new();
+        if let Some(token) = token {
+            let mut value = HeaderValue::from_str(token)?;
+            value.set_sensitive(true);
+            headers.insert(API_TOKEN_HEADER, value);
+        }
+        let mut builder = reqwest::Client::builder().default_headers(headers);
+        if let Some(path) = tls_cert {
+            let pem = std::fs::read(path)
+                .with_context(|| format!("Cannot read certificate {}", path.display()))?;
+            builder = builder.add_root_certificate(Certificate::from_pem(&pem)?);
+        }
+        let http = builder.build()?;
+        Ok(Self { http, api_url })
+    }
+
+    pub async fn create_bot(
+        &self,
+        name: &BotName,
+        source_code: &SourceCode,
+        language: &Language,
+    ) -> Result<CreateBotResult, anyhow::Error> {
+        let request = CreateBotRequest {
+            name,
+            source_code,
+            language,
+        };
+        let response = self
+            .http
+            .post(self.url("/bots"))
+            .json(&request)
+            .send()
+            .await?;
+
+        match response.status() {
+            StatusCode::CONFLICT => return Ok(CreateBotResult::DuplicateName),
+            StatusCode::FORBIDDEN => {
+                let body: ErrorResponse = response.json().await?;
+                if body.error_code == "quota_exceeded" {
+                    return Ok(CreateBotResult::QuotaExceeded);
+                }
+                bail!("{}", body.message);
+            }
+            _ => {}
+        }
+        let bot: BotMinimalResponse = parse_response(response).await?;
+        Ok(CreateBotResult::Created(bot.try_into()?))
+    }
+
+    pub async fn archive_bot(&self, id: BotId) -> Result<BotMinimal, anyhow::Error> {
+        let response = self
+            .http
+            .post(self.url(&format!("/bots/{}/archive", i64::from(id))))
+            .json(&json!({ "archived": true }))
+            .send()
+            .await?;
+
+        let bot: BotMinimalResponse = parse_response(response).await?;
+        bot.try_into()
+    }
+
+    pub async fn delete_bot(&self, id: BotId) -> Result<(), anyhow::Error> {
+        let response = self
+            .http
+            .delete(self.url(&format!("/bots/{}", i64::from(id))))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            bail!("Cannot delete bot: {}", response.status());
+        }
+        Ok(())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.api_url, path)
+    }
+}
+
+/// url of the arena running on this machine and its certificate if HTTPS is served
+pub fn local_arena_url(arena_path: &Path) -> Result<(String, Option<PathBuf>), anyhow::Error> {
+    let config = Config::load(arena_path).context("Cannot load arena config")?;
+    if config.server.port == 0 {
+        bail!("'server.port' is not set in the arena config, please pass --url explicitly");
+    }
+    match &config.server.tls_cert {
+        Some(cert) => Ok((
+            format!("https://localhost:{}", config.server.port),
+            Some(arena_path.join(cert)),
+        )),
+        None => Ok((format!("http://127.0.0.1:{}", config.server.port), None)),
+    }
+}
+
+async fn parse_response<T: for<'de> Deserialize<'de>>(
+    response: Response,
+) -> Result<T, anyhow::Error> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response.json().await?);
+    }
+    match response.json::<ErrorResponse>().await {
+        Ok(body) => bail!("{}: {}", status, body.message),
+        Err(_) => bail!("{}", status),
+    }
+}
@@ -1,33 +1,265 @@
-use anyhow::bail;
+use anyhow::{bail, Context};
+use itertools::Itertools;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
 use std::{fs::OpenOptions, io::Write, path::Path};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Deserialize)]
+#[serde(try_from = "RawConfig")]
 pub struct Config {
+    /// games hosted by the arena, the first one is the default
+    pub games: Vec<ArenaGameConfig>,
+    /// how many matches can be run in parallel across all the games
+    pub threads: usize,
+    pub server: ServerConfig,
+    pub log: LogConfig,
+    /// webhooks called on the arena events of every game
+    pub notifications: Vec<NotificationConfig>,
+}
+
+/// Config file either describes a single game at the top level or several named `[[games]]`.
+#[derive(Deserialize)]
+struct RawConfig {
+    game: Option<GameConfig>,
+    matchmaking: Option<MatchmakingConfig>,
+    ranking: Option<RankingConfig>,
+    workers: Option<Vec<WorkerConfig>>,
+    retention: Option<RetentionConfig>,
+    #[serde(default)]
+    games: Vec<ArenaGameConfig>,
+    threads: Option<usize>,
+    #[serde(default)]
+    server: ServerConfig,
+    #[serde(default)]
+    log: LogConfig,
+    #[serde(default)]
+    notifications: Vec<NotificationConfig>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArenaGameConfig {
+    pub name: String,
     pub game: GameConfig,
     pub matchmaking: MatchmakingConfig,
     pub ranking: RankingConfig,
-    #[serde(default)]
-    pub server: ServerConfig,
-    #[serde(default)]
-    pub log: LogConfig,
     pub workers: Vec<WorkerConfig>,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    /// directory of the game's database and bots relative to the arena directory
+    #[serde(skip)]
+    pub dir: PathBuf,
 }
 
-#[derive(Serialize, Deserialize)]
+impl TryFrom<RawConfig> for Config {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawConfig) -> Result<Self, Self::Error> {
+        let games = match (raw.game, raw.matchmaking, raw.ranking, raw.workers) {
+            (None, None, None, None) => {
+                if raw.retention.is_some() {
+                    bail!("[retention] should be specified per game in [[games]]");
+                }
+                let mut games = raw.games;
+                for game in &mut games {
+                    game.dir = PathBuf::from(DIR_GAMES).join(&game.name);
+                }
+                games
+            }
+            (Some(game), Some(matchmaking), Some(ranking), Some(workers)) if raw.games.is_empty() => {
+                // single game keeps its data in the arena directory
+                vec![ArenaGameConfig {
+                    name: DEFAULT_GAME_NAME.to_string(),
+                    game,
+                    matchmaking,
+                    ranking,
+                    workers,
+                    retention: raw.retention.unwrap_or_default(),
+                    dir: PathBuf::new(),
+                }]
+            }
+            _ => bail!(
+                "either [[games]] or all of [game], [matchmaking], [ranking] and [[workers]] should be specified"
+            ),
+        };
+        let threads = raw.threads.unwrap_or_else(|| {
+            games
+                .iter()
+                .flat_map(|g| &g.workers)
+                .map(|WorkerConfig::Embedded(w)| w.threads as usize)
+                .sum()
+        });
+        Ok(Config {
+            games,
+            threads,
+            server: raw.server,
+            log: raw.log,
+            notifications: raw.notifications,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct GameConfig {
+    /// amount of teams if 'team_size' > 1
     pub min_players: u32,
     pub max_players: u32,
+    /// amount of bots playing together in a team
+    #[serde(default = "default_team_size")]
+    pub team_size: u32,
     pub symmetric: bool,
+    #[serde(default)]
+    pub permutation_rating: PermutationRating,
+    /// named parameters substituted into 'cmd_play_match' as `{PARAM:name}`
+    #[serde(default)]
+    pub params: BTreeMap<String, GameParamConfig>,
 }
 
-#[derive(Serialize, Deserialize)]
+/// Value of the game parameter, either fixed or sampled uniformly for every match.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum GameParamConfig {
+    Sampled(Vec<GameParamValue>),
+    Fixed(GameParamValue),
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum GameParamValue {
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    String(String),
+}
+
+fn default_team_size() -> u32 {
+    1
+}
+
+impl GameConfig {
+    /// picks a value for every game parameter
+    pub fn sample_params(&self, rng: &mut impl Rng) -> BTreeMap<String, String> {
+        self.params
+            .iter()
+            .map(|(name, param)| {
+                let value = match param {
+                    GameParamConfig::Sampled(values) => values
+                        .choose(rng)
+                        .expect("params are validated to be non-empty"),
+                    GameParamConfig::Fixed(value) => value,
+                };
+                (name.clone(), value.to_string())
+            })
+            .collect()
+    }
+}
+
+impl Display for GameParamValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameParamValue::Integer(v) => write!(f, "{v}"),
+            GameParamValue::Float(v) => write!(f, "{v}"),
+            GameParamValue::Boolean(v) => write!(f, "{v}"),
+            GameParamValue::String(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+/// How matches played on the same seed with different seat permutations are rated
+/// when the game is not symmetric.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PermutationRating {
+    /// every match is rated on its own as soon as it finishes
+    #[default]
+    Separate,
+    /// all permutations are rated as a single match once all of them finish,
+    /// bots are ranked by the sum of their ranks
+    SumRanks,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct MatchmakingConfig {
     pub min_matches: u32,
     pub min_matches_preference: f64,
+    /// if set matches are played only on these seeds instead of random ones
+    #[serde(default)]
+    pub seeds: Option<SeedsConfig>,
+    #[serde(default)]
+    pub benchmarks: Vec<BenchmarkConfig>,
+    /// new bots with a higher error rate are paused once they played `error_check_matches`
+    #[serde(default)]
+    pub max_error_rate: Option<f64>,
+    #[serde(default = "default_error_check_matches")]
+    pub error_check_matches: usize,
+    /// submitting or unarchiving bots above the limit is rejected
+    #[serde(default)]
+    pub max_active_bots_per_author: Option<usize>,
+    /// every author gets the same share of matches regardless of the amount of their bots
+    #[serde(default)]
+    pub fair_authors: bool,
 }
 
-#[derive(Serialize, Deserialize)]
+fn default_error_check_matches() -> usize {
+    20
+}
+
+/// Explicit list of seeds or path to a file (relative to the arena directory) with one seed per line.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum SeedsConfig {
+    List(Vec<i64>),
+    File(String),
+}
+
+impl SeedsConfig {
+    /// seeds of the list, `Config::load` replaces files with their content
+    pub fn list(&self) -> &[i64] {
+        match self {
+            SeedsConfig::List(seeds) => seeds,
+            SeedsConfig::File(_) => &[],
+        }
+    }
+
+    fn resolve(&mut self, arena_path: &Path) -> Result<(), anyhow::Error> {
+        if let SeedsConfig::File(file) = self {
+            let content = std::fs::read_to_string(arena_path.join(&*file))
+                .with_context(|| format!("Cannot read seeds file '{file}'"))?;
+            *self = SeedsConfig::List(parse_seeds(&content).with_context(|| file.clone())?);
+        }
+        Ok(())
+    }
+}
+
+/// Set of seeds played exhaustively for every bot against every reference bot.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BenchmarkConfig {
+    pub name: String,
+    pub seeds: SeedsConfig,
+    pub reference_bots: Vec<String>,
+}
+
+/// Old matches are pruned from the database, the ratings and statistics they contributed to are kept.
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct RetentionConfig {
+    /// only the last matches of every set of bots playing together are kept
+    #[serde(default)]
+    pub max_matches_per_pair: Option<usize>,
+    /// matches played earlier are pruned
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+}
+
+impl RetentionConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.max_matches_per_pair.is_some() || self.max_age_days.is_some()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(tag = "algorithm")]
 pub enum RankingConfig {
     OpenSkill,
@@ -55,6 +287,56 @@ pub struct ServerConfig {
     pub port: u16,
     #[serde(default)]
     pub expose: bool,
+    /// authentication is required once at least one user is configured
+    #[serde(default)]
+    pub users: Vec<UserConfig>,
+    /// PEM files relative to the arena directory, HTTPS is served when both are set
+    #[serde(default)]
+    pub tls_cert: Option<PathBuf>,
+    #[serde(default)]
+    pub tls_key: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UserConfig {
+    pub name: String,
+    /// password in the browser and `X-Api-Token` header value for the CLI
+    pub token: String,
+    pub role: Role,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// can only view the arena
+    ReadOnly,
+    /// can also submit bots and rename, archive and delete the own ones
+    Member,
+    /// can also manage the bots of everybody, run SPRT and backups
+    Admin,
+}
+
+impl ServerConfig {
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        if self.tls_cert.is_some() != self.tls_key.is_some() {
+            bail!("tls_cert and tls_key should be set together");
+        }
+        for user in &self.users {
+            if user.name.is_empty() || user.name.contains(':') {
+                bail!("user name '{}' is invalid", user.name);
+            }
+            if user.token.is_empty() {
+                bail!("token of user '{}' should not be empty", user.name);
+            }
+        }
+        if !self.users.iter().map(|u| &u.name).all_unique() {
+            bail!("user names should be unique");
+        }
+        if !self.users.iter().map(|u| &u.token).all_unique() {
+            bail!("user tokens should be unique");
+        }
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Deserialize, Default)]
@@ -63,40 +345,248 @@ pub struct LogConfig {
     pub file: Option<String>,
 }
 
+/// JSON payload of the selected events is POSTed to the url.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NotificationConfig {
+    pub url: String,
+    pub events: Vec<NotificationEvent>,
+    /// threshold of the `matches_reached` event
+    #[serde(default)]
+    pub matches: Option<usize>,
+    /// threshold of the `error_rate_exceeded` event
+    #[serde(default)]
+    pub max_error_rate: Option<f64>,
+    /// error rate of the bots with less matches is not checked
+    #[serde(default = "default_error_rate_min_matches")]
+    pub error_rate_min_matches: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    BuildFailed,
+    MatchesReached,
+    NewLeader,
+    ErrorRateExceeded,
+}
+
+fn default_error_rate_min_matches() -> usize {
+    20
+}
+
+impl NotificationConfig {
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            bail!("url should start with http:// or https://");
+        }
+        if self.events.is_empty() {
+            bail!("events should not be empty");
+        }
+        if self.events.contains(&NotificationEvent::MatchesReached)
+            && self.matches.is_none_or(|m| m == 0)
+        {
+            bail!("matches_reached event requires positive 'matches'");
+        }
+        if self.events.contains(&NotificationEvent::ErrorRateExceeded)
+            && self
+                .max_error_rate
+                .is_none_or(|r| !(0.0..=1.0).contains(&r))
+        {
+            bail!("error_rate_exceeded event requires 'max_error_rate' in 0..1 range");
+        }
+        Ok(())
+    }
+}
+
 impl Config {
     pub fn load(arena_path: &Path) -> Result<Config, anyhow::Error> {
         let path = arena_path.join(CONFIG_FILE_NAME);
         let config_content = std::fs::read_to_string(path)?;
-        let config: Config = toml::from_str(&config_content)?;
+        let mut config: Config = toml::from_str(&config_content)?;
+        for game in &mut config.games {
+            if let Some(seeds) = &mut game.matchmaking.seeds {
+                seeds.resolve(arena_path)?;
+            }
+            for benchmark in &mut game.matchmaking.benchmarks {
+                benchmark.seeds.resolve(arena_path)?;
+            }
+        }
         Ok(config)
     }
 
     pub fn validate(&self) -> Result<(), anyhow::Error> {
-        if self.game.max_players > 8 {
-            bail!("Games with up to 8 players are supported");
+        if self.games.is_empty() {
+            bail!("at least one game should be configured");
         }
-        if self.game.min_players > self.game.max_players {
-            bail!("game.max_players must be not less than game.min_players");
+        if self.threads == 0 {
+            bail!("threads should be positive");
         }
-        if !(0.0..=1.0).contains(&self.matchmaking.min_matches_preference) {
-            bail!("matchmaking.min_matches_preference should be in 0..1 range");
+        if !self.games.iter().map(|g| &g.name).all_unique() {
+            bail!("game names should be unique");
+        }
+        for game in &self.games {
+            game.validate()
+                .with_context(|| format!("game '{}'", game.name))?;
+        }
+        self.server.validate().context("server")?;
+        for notification in &self.notifications {
+            notification
+                .validate()
+                .with_context(|| format!("notification '{}'", notification.url))?;
         }
         Ok(())
     }
 
-    pub fn create_default(arena_path: &Path) {
+    /// default game if `name` is not specified
+    pub fn find_game(&self, name: Option<&str>) -> Result<&ArenaGameConfig, anyhow::Error> {
+        match name {
+            Some(name) => self
+                .games
+                .iter()
+                .find(|g| g.name == name)
+                .with_context(|| format!("Game '{name}' is not configured")),
+            None => Ok(&self.games[0]),
+        }
+    }
+
+    /// `tls` enables HTTPS with [`TLS_CERT_FILE_NAME`] and [`TLS_KEY_FILE_NAME`]
+    pub fn create_default(arena_path: &Path, tls: bool) {
         let config_file_path = arena_path.join(CONFIG_FILE_NAME);
+        let mut content = DEFAULT_CONFIG_CONTENT.to_string();
+        if tls {
+            content = content
+                .replace(
+                    &format!("# tls_cert = \"{TLS_CERT_FILE_NAME}\""),
+                    &format!("tls_cert = \"{TLS_CERT_FILE_NAME}\""),
+                )
+                .replace(
+                    &format!("# tls_key = \"{TLS_KEY_FILE_NAME}\""),
+                    &format!("tls_key = \"{TLS_KEY_FILE_NAME}\""),
+                );
+        }
         OpenOptions::new()
             .write(true)
             .create_new(true)
             .open(config_file_path)
             .expect("Cannot create config file")
-            .write_all(DEFAULT_CONFIG_CONTENT.as_bytes())
+            .write_all(content.as_bytes())
             .expect("Cannot write default config");
     }
 }
 
+impl ArenaGameConfig {
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        if self.name.is_empty()
+            || !self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            bail!("game name should consist of latin letters, digits, '-' and '_'");
+        }
+        if self.game.team_size == 0 {
+            bail!("game.team_size should be positive");
+        }
+        if self.game.max_players * self.game.team_size > 8 {
+            bail!("Games with up to 8 bots are supported");
+        }
+        if self.game.min_players > self.game.max_players {
+            bail!("game.max_players must be not less than game.min_players");
+        }
+        for (name, param) in &self.game.params {
+            if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '}') {
+                bail!("game.params name '{name}' is invalid");
+            }
+            if matches!(param, GameParamConfig::Sampled(values) if values.is_empty()) {
+                bail!("game.params.{name} should have at least one value");
+            }
+        }
+        for worker in &self.workers {
+            let WorkerConfig::Embedded(worker) = worker;
+            for part in worker.cmd_play_match.split_ascii_whitespace() {
+                if let Some(name) = param_placeholder(part) {
+                    if !self.game.params.contains_key(name) {
+                        bail!("cmd_play_match uses unknown game parameter '{name}'");
+                    }
+                }
+            }
+        }
+        if !(0.0..=1.0).contains(&self.matchmaking.min_matches_preference) {
+            bail!("matchmaking.min_matches_preference should be in 0..1 range");
+        }
+        if self
+            .matchmaking
+            .max_error_rate
+            .is_some_and(|r| !(0.0..=1.0).contains(&r))
+        {
+            bail!("matchmaking.max_error_rate should be in 0..1 range");
+        }
+        if self.matchmaking.error_check_matches == 0 {
+            bail!("matchmaking.error_check_matches should be positive");
+        }
+        if self.matchmaking.max_active_bots_per_author == Some(0) {
+            bail!("matchmaking.max_active_bots_per_author should be positive");
+        }
+        if self
+            .matchmaking
+            .seeds
+            .as_ref()
+            .is_some_and(|s| s.list().is_empty())
+        {
+            bail!("matchmaking.seeds should not be empty");
+        }
+        for benchmark in &self.matchmaking.benchmarks {
+            if !(self.game.min_players..=self.game.max_players).contains(&2)
+                || self.game.team_size != 1
+            {
+                bail!("matchmaking.benchmarks are supported only for games allowing 2 players");
+            }
+            if benchmark.seeds.list().is_empty() {
+                bail!("benchmark '{}' should have seeds", benchmark.name);
+            }
+            if benchmark.reference_bots.is_empty() {
+                bail!("benchmark '{}' should have reference bots", benchmark.name);
+            }
+        }
+        if !self
+            .matchmaking
+            .benchmarks
+            .iter()
+            .map(|b| &b.name)
+            .all_unique()
+        {
+            bail!("benchmark names should be unique");
+        }
+        if self.retention.max_matches_per_pair == Some(0) {
+            bail!("retention.max_matches_per_pair should be positive");
+        }
+        if self.retention.max_age_days == Some(0) {
+            bail!("retention.max_age_days should be positive");
+        }
+        Ok(())
+    }
+}
+
+/// name of the game parameter if `part` of the command is `{PARAM:name}`
+pub fn param_placeholder(part: &str) -> Option<&str> {
+    part.strip_prefix("{PARAM:")?.strip_suffix('}')
+}
+
+/// one seed per line, empty lines and lines starting with '#' are ignored
+fn parse_seeds(content: &str) -> Result<Vec<i64>, anyhow::Error> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| l.parse().with_context(|| format!("Invalid seed '{l}'")))
+        .collect()
+}
+
 const CONFIG_FILE_NAME: &str = "cgarena_config.toml";
+pub const TLS_CERT_FILE_NAME: &str = "cert.pem";
+pub const TLS_KEY_FILE_NAME: &str = "key.pem";
+const DIR_GAMES: &str = "games";
+const DEFAULT_GAME_NAME: &str = "default";
 
 static DEFAULT_CONFIG_CONTENT: &str = include_str!(concat!(
     env!("CARGO_MANIFEST_DIR"),
@@ -109,6 +599,80 @@ mod test {
 
     #[test]
     fn default_config_is_valid() {
-        let _: Config = toml::from_str(DEFAULT_CONFIG_CONTENT).expect("to be a valid config");
+        let config: Config = toml::from_str(DEFAULT_CONFIG_CONTENT).expect("to be a valid config");
+        config.validate().expect("to be a valid config");
+        assert_eq!(config.games.len(), 1);
+        assert_eq!(config.games[0].dir, PathBuf::new());
+    }
+
+    #[test]
+    fn several_games_can_be_configured() {
+        let config: Config = toml::from_str(indoc::indoc! {r#"
+            [[games]]
+            name = "spring"
+            game = { min_players = 2, max_players = 2, symmetric = true }
+            matchmaking = { min_matches = 10, min_matches_preference = 1.0 }
+            ranking = { algorithm = "OpenSkill" }
+            workers = [{ type = "embedded", threads = 2, cmd_play_match = "a", cmd_build = "b", cmd_run = "c" }]
+
+            [[games]]
+            name = "winter"
+            game = { min_players = 2, max_players = 4, symmetric = false }
+            matchmaking = { min_matches = 10, min_matches_preference = 1.0 }
+            ranking = { algorithm = "OpenSkill" }
+            workers = [{ type = "embedded", threads = 3, cmd_play_match = "a", cmd_build = "b", cmd_run = "c" }]
+        "#})
+        .unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.threads, 5);
+        assert_eq!(config.games[1].dir, Path::new("games").join("winter"));
+        assert_eq!(config.find_game(None).unwrap().name, "spring");
+        assert!(config.find_game(Some("autumn")).is_err());
+    }
+
+    #[test]
+    fn retention_is_configured_per_game() {
+        let game = indoc::indoc! {r#"
+            game = { min_players = 2, max_players = 2, symmetric = true }
+            matchmaking = { min_matches = 10, min_matches_preference = 1.0 }
+            ranking = { algorithm = "OpenSkill" }
+            workers = [{ type = "embedded", threads = 2, cmd_play_match = "a", cmd_build = "b", cmd_run = "c" }]
+        "#};
+        let config: Config =
+            toml::from_str(&format!("{game}\n[retention]\nmax_matches_per_pair = 10\n")).unwrap();
+        config.validate().unwrap();
+        assert!(config.games[0].retention.is_enabled());
+
+        let config: Config =
+            toml::from_str(&format!("{game}\n[retention]\nmax_age_days = 0\n")).unwrap();
+        assert!(config.validate().is_err());
+
+        let games = format!("[retention]\nmax_age_days = 1\n[[games]]\nname = \"spring\"\n{game}");
+        assert!(toml::from_str::<Config>(&games).is_err());
+    }
+
+    #[test]
+    fn game_params_can_be_parsed() {
+        let config: GameConfig = toml::from_str(indoc::indoc! {r#"
+            min_players = 2
+            max_players = 2
+            symmetric = true
+            [params]
+            league = [1, 2, 3]
+            size = "large"
+        "#})
+        .unwrap();
+        let params = config.sample_params(&mut rand::thread_rng());
+        assert!(["1", "2", "3"].contains(&params["league"].as_str()));
+        assert_eq!(params["size"], "large");
+        assert_eq!(param_placeholder("{PARAM:size}"), Some("size"));
+        assert_eq!(param_placeholder("{SEED}"), None);
+    }
+
+    #[test]
+    fn seeds_can_be_parsed() {
+        let seeds = parse_seeds("# maps\n1\n\n -2 \n").unwrap();
+        assert_eq!(seeds, [1, -2]);
+        assert!(parse_seeds("abc").is_err());
     }
 }
@@ -1,11 +1,17 @@
-use crate::domain::{Bot, BotId, Build, BuildResult, BuildStatus, Match, MatchId, Participant};
-use anyhow::bail;
+use crate::domain::{
+    Bot, BotId, Build, BuildResult, BuildStatus, ErrorKind, Match, MatchId, Participant,
+};
+use anyhow::{bail, Context};
 use chrono::{DateTime, Utc};
 use indoc::indoc;
-use sqlx::sqlite::SqliteConnectOptions;
-use sqlx::{migrate::MigrateDatabase, ConnectOptions, Connection, Sqlite, SqliteConnection};
-use std::collections::HashMap;
-use std::path::Path;
+use itertools::Itertools;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use tokio_stream::{Stream, StreamExt};
 use tracing::warn;
 
 #[derive(sqlx::FromRow)]
@@ -15,6 +21,9 @@ struct BotsRow {
     pub source_code: String,
     pub language: String,
     pub created_at: DateTime<Utc>,
+    pub archived: bool,
+    pub author: Option<String>,
+    pub paused_reason: Option<String>,
 }
 
 #[derive(sqlx::FromRow)]
@@ -22,6 +31,8 @@ struct MatchesRow {
     pub id: i64,
     pub seed: i64,
     pub participant_cnt: u8,
+    pub group_id: Option<i64>,
+    pub created_at: i64,
 }
 
 #[derive(sqlx::FromRow)]
@@ -31,6 +42,54 @@ struct ParticipationsRow {
     pub index: u8,
     pub rank: u8,
     pub error: bool,
+    /// null for the matches played before team games were supported
+    pub team: Option<u8>,
+    pub score: Option<f64>,
+    pub error_kind: Option<String>,
+    pub error_message: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct MatchParamsRow {
+    pub match_id: i64,
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct ParticipationMetricsRow {
+    pub match_id: i64,
+    pub bot_id: i64,
+    pub name: String,
+    pub value: f64,
+}
+
+/// match joined with one of its participations
+#[derive(sqlx::FromRow)]
+struct MatchParticipationRow {
+    #[sqlx(flatten)]
+    pub m: MatchesRow,
+    #[sqlx(flatten)]
+    pub p: ParticipationsRow,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct ExpiredMatchRow {
+    pub id: i64,
+    pub seed: i64,
+    pub group_id: Option<i64>,
+    /// false for the matches of the same group which are not expired
+    pub expired: bool,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct ComputedStatsRow {
+    pub last_match_id: i64,
+    pub data: String,
+    /// null for the snapshots made before the settings were stored
+    pub rating_settings: Option<String>,
+    /// whether some of the covered matches were deleted, so the stats can't be recalculated
+    pub has_pruned: bool,
 }
 
 #[derive(sqlx::FromRow)]
@@ -71,24 +130,45 @@ impl TryFrom<BotsRow> for Bot {
             source_code: bot.source_code.try_into()?,
             language: bot.language.try_into()?,
             created_at: bot.created_at,
+            archived: bot.archived,
+            paused_reason: bot.paused_reason,
+            author: bot.author,
         })
     }
 }
 
-impl From<ParticipationsRow> for Participant {
-    fn from(row: ParticipationsRow) -> Self {
-        Participant {
+impl TryFrom<ParticipationsRow> for Participant {
+    type Error = anyhow::Error;
+
+    fn try_from(row: ParticipationsRow) -> Result<Self, Self::Error> {
+        Ok(Participant {
             bot_id: row.bot_id.into(),
             rank: row.rank,
             error: row.error,
-        }
+            error_kind: row
+                .error_kind
+                .as_deref()
+                .map(ErrorKind::try_from)
+                .transpose()?,
+            error_message: row.error_message,
+            team: row.team.unwrap_or(row.index),
+            score: row.score,
+            metrics: BTreeMap::new(),
+        })
     }
 }
 
-impl TryFrom<(MatchesRow, Vec<ParticipationsRow>)> for Match {
+type MatchRows = (
+    MatchesRow,
+    Vec<ParticipationsRow>,
+    Vec<MatchParamsRow>,
+    Vec<ParticipationMetricsRow>,
+);
+
+impl TryFrom<MatchRows> for Match {
     type Error = anyhow::Error;
 
-    fn try_from((m, mut ps): (MatchesRow, Vec<ParticipationsRow>)) -> Result<Self, Self::Error> {
+    fn try_from((m, mut ps, params, metrics): MatchRows) -> Result<Self, Self::Error> {
         if m.participant_cnt as usize != ps.len() {
             bail!("participant count mismatch");
         }
@@ -98,45 +178,106 @@ impl TryFrom<(MatchesRow, Vec<ParticipationsRow>)> for Match {
                 bail!("Some participation index is missing");
             }
         }
+        let mut participants: Vec<Participant> =
+            ps.into_iter().map(Participant::try_from).try_collect()?;
+        for metric in metrics {
+            let bot_id = metric.bot_id.into();
+            let Some(p) = participants.iter_mut().find(|p| p.bot_id == bot_id) else {
+                bail!("Metric does not match any participation");
+            };
+            p.metrics.insert(metric.name, metric.value);
+        }
         Ok(Match {
             id: m.id.into(),
             seed: m.seed,
-            participants: ps.into_iter().map(|p| p.into()).collect(),
+            participants,
+            group_id: m.group_id,
+            params: params.into_iter().map(|p| (p.name, p.value)).collect(),
+            created_at: DateTime::from_timestamp(m.created_at, 0).context("Invalid created_at")?,
         })
     }
 }
 
+/// Cloned handles share the connection pool, so the API can read while the arena writes.
+#[derive(Clone)]
 pub struct Database {
-    conn: SqliteConnection,
+    pool: SqlitePool,
+    /// directory of the database file
+    dir: PathBuf,
 }
 
 const DB_FILE_NAME: &str = "cgarena.db";
+const DIR_BACKUPS: &str = "backups";
+/// sqlite has a single writer anyway, more connections only serve more concurrent reads
+const MAX_CONNECTIONS: u32 = 4;
 
 impl Database {
     pub async fn connect(arena_path: &Path) -> Self {
-        let db_path = arena_path.join(DB_FILE_NAME);
-        let db_url = format!("sqlite://{}", db_path.display());
-
-        if !Sqlite::database_exists(&db_url).await.unwrap_or(false) {
-            Sqlite::create_database(&db_url)
-                .await
-                .expect("cannot create database");
-        }
-        let mut conn = SqliteConnectOptions::new()
-            .filename(&db_path)
-            .connect()
+        let options = SqliteConnectOptions::new()
+            .filename(arena_path.join(DB_FILE_NAME))
+            .create_if_missing(true)
+            // readers don't block the writer and vice versa
+            .journal_mode(SqliteJournalMode::Wal);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(MAX_CONNECTIONS)
+            .connect_with(options)
             .await
             .expect("cannot connect to database");
 
         sqlx::migrate!()
-            .run(&mut conn)
+            .run(&pool)
             .await
             .expect("can't run migrations");
 
-        Self { conn }
+        Self {
+            pool,
+            dir: arena_path.to_path_buf(),
+        }
     }
 
-    pub async fn persist_bot(&mut self, bot: &mut Bot) {
+    /// opens the database of a possibly running arena without creating or migrating it
+    pub async fn connect_read_only(arena_path: &Path) -> Result<Self, anyhow::Error> {
+        let path = arena_path.join(DB_FILE_NAME);
+        let options = SqliteConnectOptions::new().filename(&path).read_only(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(MAX_CONNECTIONS)
+            .connect_with(options)
+            .await
+            .with_context(|| format!("Cannot open {}", path.display()))?;
+
+        let latest = sqlx::migrate!().iter().map(|m| m.version).max();
+        let applied: Option<i64> =
+            sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1")
+                .fetch_one(&pool)
+                .await
+                .unwrap_or_default();
+        if applied < latest {
+            bail!("Database is outdated, start the arena once to upgrade it");
+        }
+
+        Ok(Self {
+            pool,
+            dir: arena_path.to_path_buf(),
+        })
+    }
+
+    /// consistent copy of the database made with `VACUUM INTO` while it is in use,
+    /// returns the path of the copy. It reads a WAL snapshot, so writes are not blocked meanwhile.
+    pub async fn backup(&self) -> Result<PathBuf, anyhow::Error> {
+        let dir = self.dir.join(DIR_BACKUPS);
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!(
+            "cgarena-{}.db",
+            Utc::now().format("%Y%m%d-%H%M%S%.3f")
+        ));
+        sqlx::query("VACUUM INTO $1")
+            .bind::<&str>(&path.to_string_lossy())
+            .execute(&self.pool)
+            .await?;
+        Ok(path)
+    }
+
+    pub async fn persist_bot(&self, bot: &mut Bot) {
         if bot.id == BotId::UNINITIALIZED {
             bot.id = self.insert_bot(bot).await;
         } else {
@@ -144,11 +285,11 @@ impl Database {
         }
     }
 
-    async fn insert_bot(&mut self, bot: &Bot) -> BotId {
+    async fn insert_bot(&self, bot: &Bot) -> BotId {
         assert_eq!(bot.id, BotId::UNINITIALIZED);
         const SQL: &str = indoc! {"
-            INSERT INTO bots (name, source_code, language, created_at) \
-            VALUES ($1, $2, $3, $4) \
+            INSERT INTO bots (name, source_code, language, created_at, archived, author, paused_reason) \
+            VALUES ($1, $2, $3, $4, $5, $6, $7) \
         "};
 
         let res = sqlx::query(SQL)
@@ -156,7 +297,10 @@ impl Database {
             .bind::<&str>(&bot.source_code)
             .bind::<&str>(&bot.language)
             .bind::<DateTime<Utc>>(bot.created_at)
-            .execute(&mut self.conn)
+            .bind::<bool>(bot.archived)
+            .bind::<Option<&str>>(bot.author.as_deref())
+            .bind::<Option<&str>>(bot.paused_reason.as_deref())
+            .execute(&self.pool)
             .await
             .expect("Cannot insert bot to db");
 
@@ -164,34 +308,36 @@ impl Database {
     }
 
     /// only updates mutable fields
-    async fn update_bot(&mut self, bot: &Bot) {
+    async fn update_bot(&self, bot: &Bot) {
         assert_ne!(bot.id, BotId::UNINITIALIZED);
         const SQL: &str = indoc! {"
-            UPDATE bots SET name = $1 \
-            WHERE id = $2"
+            UPDATE bots SET name = $1, archived = $2, paused_reason = $3 \
+            WHERE id = $4"
         };
 
         let res = sqlx::query(SQL)
             .bind::<&str>(&bot.name)
+            .bind::<bool>(bot.archived)
+            .bind::<Option<&str>>(bot.paused_reason.as_deref())
             .bind::<i64>(bot.id.into())
-            .execute(&mut self.conn)
+            .execute(&self.pool)
             .await
             .expect("Cannot update bot in db");
 
         assert_eq!(res.rows_affected(), 1);
     }
 
-    pub async fn delete_bot(&mut self, id: BotId) {
+    pub async fn delete_bot(&self, id: BotId) {
         sqlx::query("DELETE FROM bots WHERE id = $1")
             .bind::<i64>(id.into())
-            .execute(&mut self.conn)
+            .execute(&self.pool)
             .await
             .expect("Cannot delete bot from db");
     }
 
-    pub async fn fetch_bots(&mut self) -> Vec<Bot> {
+    pub async fn fetch_bots(&self) -> Vec<Bot> {
         sqlx::query_as::<_, BotsRow>("SELECT * from bots")
-            .fetch_all(&mut self.conn)
+            .fetch_all(&self.pool)
             .await
             .expect("Cannot fetch all bots")
             .into_iter()
@@ -204,9 +350,9 @@ impl Database {
             .collect()
     }
 
-    pub async fn fetch_builds(&mut self) -> Vec<Build> {
+    pub async fn fetch_builds(&self) -> Vec<Build> {
         sqlx::query_as::<_, BuildsRow>("SELECT * from builds")
-            .fetch_all(&mut self.conn)
+            .fetch_all(&self.pool)
             .await
             .expect("Cannot fetch all builds")
             .into_iter()
@@ -219,7 +365,7 @@ impl Database {
             .collect()
     }
 
-    pub async fn persist_build(&mut self, build: &Build) {
+    pub async fn persist_build(&self, build: &Build) {
         const SQL: &str = indoc! {"
             INSERT OR REPLACE INTO builds (bot_id, worker_name, status, result, error) \
             VALUES ($1, $2, $3, $4, $5) \
@@ -240,33 +386,42 @@ impl Database {
             .bind::<u8>(status)
             .bind::<Option<u8>>(result)
             .bind::<Option<&str>>(error)
-            .execute(&mut self.conn)
+            .execute(&self.pool)
             .await
             .expect("Cannot upsert build to db");
     }
 
-    pub async fn persist_match(&mut self, m: &mut Match) {
-        assert_eq!(m.id, MatchId::UNINITIALIZED);
-        m.id = self.create_match(m).await;
+    /// all the matches are written in a single transaction, which is much faster than one by one
+    pub async fn persist_matches(&self, matches: &mut [Match]) {
+        let mut tx = self.pool.begin().await.expect("cannot start a transaction");
+        for m in matches {
+            assert_eq!(m.id, MatchId::UNINITIALIZED);
+            m.id = Self::insert_match(&mut tx, m).await;
+        }
+        tx.commit().await.expect("cannot commit transaction");
     }
 
-    pub async fn create_match(&mut self, m: &Match) -> MatchId {
-        let mut tx = self.conn.begin().await.expect("cannot start a transaction");
-
-        let match_id: MatchId =
-            sqlx::query("INSERT INTO matches (seed, participant_cnt) VALUES ($1, $2)")
-                .bind::<i64>(m.seed)
-                .bind::<u8>(m.participants.len() as _)
-                .execute(&mut *tx)
-                .await
-                .expect("Cannot create match in db")
-                .last_insert_rowid()
-                .into();
+    async fn insert_match(conn: &mut SqliteConnection, m: &Match) -> MatchId {
+        const SQL: &str = indoc! {
+            "INSERT INTO matches (seed, participant_cnt, group_id, created_at) \
+             VALUES ($1, $2, $3, $4)"
+        };
+        let match_id: MatchId = sqlx::query(SQL)
+            .bind::<i64>(m.seed)
+            .bind::<u8>(m.participants.len() as _)
+            .bind::<Option<i64>>(m.group_id)
+            .bind::<i64>(m.created_at.timestamp())
+            .execute(&mut *conn)
+            .await
+            .expect("Cannot create match in db")
+            .last_insert_rowid()
+            .into();
 
         for (index, p) in m.participants.iter().enumerate() {
             const SQL: &str = indoc! {
-                "INSERT INTO participations (match_id, bot_id, `index`, rank, error) \
-                 VALUES ($1, $2, $3, $4, $5)"
+                "INSERT INTO participations \
+                 (match_id, bot_id, `index`, rank, error, team, score, error_kind, error_message) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
             };
 
             sqlx::query(SQL)
@@ -275,46 +430,398 @@ impl Database {
                 .bind::<u8>(index as _)
                 .bind::<u8>(p.rank)
                 .bind::<bool>(p.error)
-                .execute(&mut *tx)
+                .bind::<u8>(p.team)
+                .bind::<Option<f64>>(p.score)
+                .bind::<Option<&str>>(p.error_kind.as_ref().map(ErrorKind::as_str))
+                .bind::<Option<&str>>(p.error_message.as_deref())
+                .execute(&mut *conn)
                 .await
                 .expect("Cannot create participation in db");
+
+            for (name, value) in &p.metrics {
+                const SQL: &str = indoc! {
+                    "INSERT INTO participation_metrics (match_id, bot_id, name, value) \
+                     VALUES ($1, $2, $3, $4)"
+                };
+
+                sqlx::query(SQL)
+                    .bind::<i64>(match_id.into())
+                    .bind::<i64>(p.bot_id.into())
+                    .bind::<&str>(name)
+                    .bind::<f64>(*value)
+                    .execute(&mut *conn)
+                    .await
+                    .expect("Cannot create participation metric in db");
+            }
+        }
+
+        for (name, value) in &m.params {
+            sqlx::query("INSERT INTO match_params (match_id, name, value) VALUES ($1, $2, $3)")
+                .bind::<i64>(match_id.into())
+                .bind::<&str>(name)
+                .bind::<&str>(value)
+                .execute(&mut *conn)
+                .await
+                .expect("Cannot create match param in db");
         }
 
-        tx.commit().await.expect("cannot commit transaction");
         match_id
     }
 
-    pub async fn fetch_matches(&mut self) -> Vec<Match> {
-        let matches: Vec<MatchesRow> = sqlx::query_as("SELECT * from matches")
-            .fetch_all(&mut self.conn)
+    /// matches played after `after` (all of them if `None`) ordered by id, so that ratings are
+    /// recalculated in the order of playing. All the tables are streamed in the order of match ids
+    /// and merged along the way, so only the resulting matches are held in memory.
+    pub async fn fetch_matches(&self, after: Option<MatchId>) -> Vec<Match> {
+        let after = after.map_or(0, i64::from);
+        // every stream holds a pool connection until it is finished
+        let mut params = OrderedRows::new(
+            sqlx::query_as("SELECT * FROM match_params WHERE match_id > $1 ORDER BY match_id")
+                .bind(after)
+                .fetch(&self.pool),
+            |row: &MatchParamsRow| row.match_id,
+        );
+        let mut metrics = OrderedRows::new(
+            sqlx::query_as(
+                "SELECT * FROM participation_metrics WHERE match_id > $1 ORDER BY match_id",
+            )
+            .bind(after)
+            .fetch(&self.pool),
+            |row: &ParticipationMetricsRow| row.match_id,
+        );
+
+        const SQL: &str = indoc! {"
+            SELECT * FROM matches \
+            JOIN participations ON participations.match_id = matches.id \
+            WHERE matches.id > $1 \
+            ORDER BY matches.id
+        "};
+        let mut matches = vec![];
+        let mut current: Option<(MatchesRow, Vec<ParticipationsRow>)> = None;
+        let mut rows = sqlx::query_as::<_, MatchParticipationRow>(SQL)
+            .bind(after)
+            .fetch(&self.pool);
+        let mut done = false;
+        while !done {
+            let row = rows
+                .next()
+                .await
+                .map(|row| row.expect("Cannot query matches from db"));
+            done = row.is_none();
+            let finished = match row {
+                Some(row) => match &mut current {
+                    Some((m, ps)) if m.id == row.p.match_id => {
+                        ps.push(row.p);
+                        continue;
+                    }
+                    _ => current.replace((row.m, vec![row.p])),
+                },
+                None => current.take(),
+            };
+            let Some((m, ps)) = finished else {
+                continue;
+            };
+
+            let id = m.id;
+            let item = (m, ps, params.take(id).await, metrics.take(id).await);
+            match Match::try_from(item) {
+                Ok(m) => matches.push(m),
+                Err(e) => warn!("Invalid db data (match {}): {}. Skipping.", id, e),
+            }
+        }
+        matches
+    }
+
+    pub async fn count_matches(&self) -> usize {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM matches")
+            .fetch_one(&self.pool)
             .await
-            .expect("Cannot query matches from db");
+            .expect("Cannot query matches from db") as usize
+    }
 
-        let participations: Vec<ParticipationsRow> = sqlx::query_as("SELECT * from participations")
-            .fetch_all(&mut self.conn)
+    pub async fn fetch_max_group_id(&self) -> Option<i64> {
+        sqlx::query_scalar("SELECT MAX(group_id) FROM matches")
+            .fetch_one(&self.pool)
             .await
-            .expect("Cannot query matches from db");
+            .expect("Cannot query matches from db")
+    }
 
-        let mut combined = HashMap::with_capacity(matches.len());
-        for m in matches {
-            combined.insert(m.id, (m, vec![]));
-        }
-        for p in participations {
-            combined
-                .get_mut(&p.match_id)
-                .expect("Participation does not match any match in db")
-                .1
-                .push(p);
+    /// bots (ordered by id) and seeds of the two-player matches played on one of `seeds`
+    pub async fn fetch_two_player_matchups(
+        &self,
+        seeds: &HashSet<i64>,
+    ) -> Vec<(BotId, BotId, i64)> {
+        const SQL: &str = indoc! {"
+            SELECT MIN(participations.bot_id), MAX(participations.bot_id), matches.seed \
+            FROM matches \
+            JOIN participations ON participations.match_id = matches.id \
+            WHERE matches.participant_cnt = 2 \
+            GROUP BY matches.id
+        "};
+        let mut matchups = vec![];
+        let mut rows = sqlx::query_as::<_, (i64, i64, i64)>(SQL).fetch(&self.pool);
+        while let Some(row) = rows.next().await {
+            let (a, b, seed) = row.expect("Cannot query matches from db");
+            if seeds.contains(&seed) {
+                matchups.push((a.into(), b.into(), seed));
+            }
         }
+        matchups
+    }
 
-        combined
-            .into_values()
-            .filter_map(|item| {
-                let id = item.0.id;
+    /// matches expired by any of the limits, together with the other matches of their groups
+    pub async fn fetch_expired_matches(
+        &self,
+        max_matches_per_pair: Option<usize>,
+        played_before: Option<DateTime<Utc>>,
+    ) -> Vec<ExpiredMatchRow> {
+        const SQL: &str = indoc! {"
+            WITH pairs AS (
+                SELECT match_id, group_concat(bot_id, ',' ORDER BY bot_id) AS bots
+                FROM participations
+                GROUP BY match_id
+            ),
+            ranked AS (
+                SELECT matches.id, matches.seed, matches.group_id,
+                       COALESCE(ROW_NUMBER() OVER (PARTITION BY pairs.bots ORDER BY matches.id DESC) > $1, 0)
+                           OR COALESCE(matches.created_at < $2, 0) AS expired
+                FROM matches
+                JOIN pairs ON pairs.match_id = matches.id
+            )
+            SELECT * FROM ranked
+            WHERE expired OR group_id IN (SELECT group_id FROM ranked WHERE expired)
+        "};
+        sqlx::query_as(SQL)
+            .bind(max_matches_per_pair.map(|max| max as i64))
+            .bind(played_before.map(|time| time.timestamp()))
+            .fetch_all(&self.pool)
+            .await
+            .expect("Cannot query matches from db")
+    }
+
+    /// the most recent matches first, only the ones played by `bot_id` and with `params` values
+    pub async fn fetch_latest_matches(
+        &self,
+        bot_id: Option<BotId>,
+        params: &BTreeMap<String, String>,
+        limit: usize,
+    ) -> Result<Vec<Match>, anyhow::Error> {
+        let filtered = |select: &str| {
+            let mut query = QueryBuilder::<Sqlite>::new(select);
+            query.push(" IN (");
+            push_latest_match_ids(&mut query, bot_id, params, limit);
+            query.push(")");
+            query
+        };
+        // the read transaction makes all the queries see the same matches
+        let mut tx = self.pool.begin().await?;
+        let matches: Vec<MatchesRow> = filtered("SELECT * FROM matches WHERE id")
+            .push(" ORDER BY id DESC")
+            .build_query_as()
+            .fetch_all(&mut *tx)
+            .await?;
+        if matches.is_empty() {
+            return Ok(vec![]);
+        }
+        let participations: Vec<ParticipationsRow> =
+            filtered("SELECT * FROM participations WHERE match_id")
+                .build_query_as()
+                .fetch_all(&mut *tx)
+                .await?;
+        let params: Vec<MatchParamsRow> = filtered("SELECT * FROM match_params WHERE match_id")
+            .build_query_as()
+            .fetch_all(&mut *tx)
+            .await?;
+        let metrics: Vec<ParticipationMetricsRow> =
+            filtered("SELECT * FROM participation_metrics WHERE match_id")
+                .build_query_as()
+                .fetch_all(&mut *tx)
+                .await?;
+        tx.commit().await?;
+
+        let mut participations = participations.into_iter().into_group_map_by(|p| p.match_id);
+        let mut params = params.into_iter().into_group_map_by(|p| p.match_id);
+        let mut metrics = metrics.into_iter().into_group_map_by(|p| p.match_id);
+        Ok(matches
+            .into_iter()
+            .filter_map(|m| {
+                let id = m.id;
+                let item = (
+                    m,
+                    participations.remove(&id).unwrap_or_default(),
+                    params.remove(&id).unwrap_or_default(),
+                    metrics.remove(&id).unwrap_or_default(),
+                );
                 Match::try_from(item)
                     .inspect_err(|e| warn!("Invalid db data (match {}): {}. Skipping.", id, e))
                     .ok()
             })
-            .collect()
+            .collect())
+    }
+
+    pub async fn fetch_bot_names(&self) -> Result<HashMap<BotId, String>, anyhow::Error> {
+        let rows: Vec<(i64, String)> = sqlx::query_as("SELECT id, name FROM bots")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, name)| (BotId::from(id), name))
+            .collect())
+    }
+
+    /// `None` when the bot does not exist, otherwise its author if any
+    pub async fn fetch_bot_author(
+        &self,
+        id: BotId,
+    ) -> Result<Option<Option<String>>, anyhow::Error> {
+        Ok(sqlx::query_scalar("SELECT author FROM bots WHERE id = $1")
+            .bind::<i64>(id.into())
+            .fetch_optional(&self.pool)
+            .await?)
+    }
+
+    /// the persisted stats snapshot, there is at most one per game
+    pub async fn fetch_stats_snapshot(&self) -> Option<ComputedStatsRow> {
+        sqlx::query_as::<_, ComputedStatsRow>("SELECT * FROM computed_stats")
+            .fetch_optional(&self.pool)
+            .await
+            .expect("Cannot query computed stats from db")
+    }
+
+    pub async fn persist_stats_snapshot(&self, snapshot: &ComputedStatsRow) {
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .expect("cannot acquire a connection");
+        Self::upsert_stats_snapshot(&mut conn, snapshot).await;
+    }
+
+    pub async fn delete_stats_snapshot(&self) {
+        sqlx::query("DELETE FROM computed_stats")
+            .execute(&self.pool)
+            .await
+            .expect("Cannot delete computed stats from db");
+    }
+
+    /// the snapshot should already cover the deleted matches, so both are stored together
+    pub async fn prune_matches(&self, ids: &[MatchId], snapshot: &ComputedStatsRow) {
+        let mut tx = self.pool.begin().await.expect("cannot start a transaction");
+        Self::upsert_stats_snapshot(&mut tx, snapshot).await;
+        // participations, params and metrics are deleted by foreign key constraints
+        for id in ids {
+            sqlx::query("DELETE FROM matches WHERE id = $1")
+                .bind::<i64>((*id).into())
+                .execute(&mut *tx)
+                .await
+                .expect("Cannot delete match from db");
+        }
+        tx.commit().await.expect("cannot commit transaction");
+    }
+
+    async fn upsert_stats_snapshot(conn: &mut SqliteConnection, snapshot: &ComputedStatsRow) {
+        const SQL: &str = indoc! {
+            "INSERT OR REPLACE INTO computed_stats \
+             (id, last_match_id, data, rating_settings, has_pruned) \
+             VALUES (1, $1, $2, $3, $4)"
+        };
+        sqlx::query(SQL)
+            .bind::<i64>(snapshot.last_match_id)
+            .bind::<&str>(&snapshot.data)
+            .bind::<Option<&str>>(snapshot.rating_settings.as_deref())
+            .bind::<bool>(snapshot.has_pruned)
+            .execute(conn)
+            .await
+            .expect("Cannot upsert computed stats to db");
+    }
+
+    /// rebuilds the database file to reclaim the space of the deleted rows, returns the sizes before and after
+    pub async fn vacuum(&self) -> Result<(u64, u64), anyhow::Error> {
+        let path = self.dir.join(DB_FILE_NAME);
+        self.checkpoint().await?;
+        let size_before = std::fs::metadata(&path)?.len();
+        sqlx::query("VACUUM").execute(&self.pool).await?;
+        self.checkpoint().await?;
+        let size_after = std::fs::metadata(&path)?.len();
+        Ok((size_before, size_after))
+    }
+
+    /// moves the pages from the WAL file to the database file
+    async fn checkpoint(&self) -> Result<(), anyhow::Error> {
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// ids of the latest `limit` matches passing the filters, scanned from the latest one
+fn push_latest_match_ids(
+    query: &mut QueryBuilder<'_, Sqlite>,
+    bot_id: Option<BotId>,
+    params: &BTreeMap<String, String>,
+    limit: usize,
+) {
+    query.push("SELECT id FROM matches WHERE 1 = 1");
+    if let Some(bot_id) = bot_id {
+        query
+            .push(" AND EXISTS (SELECT 1 FROM participations")
+            .push(" WHERE match_id = matches.id AND bot_id = ")
+            .push_bind(i64::from(bot_id))
+            .push(")");
+    }
+    for (name, value) in params {
+        query
+            .push(" AND EXISTS (SELECT 1 FROM match_params")
+            .push(" WHERE match_id = matches.id AND name = ")
+            .push_bind(name.clone())
+            .push(" AND value = ")
+            .push_bind(value.clone())
+            .push(")");
+    }
+    query
+        .push(" ORDER BY id DESC LIMIT ")
+        .push_bind(i64::try_from(limit).unwrap_or(i64::MAX));
+}
+
+/// rows of a table ordered by match id, read along with the matches
+struct OrderedRows<'a, T> {
+    rows: Pin<Box<dyn Stream<Item = Result<T, sqlx::Error>> + Send + 'a>>,
+    match_id: fn(&T) -> i64,
+    /// the first row of the next match
+    ahead: Option<T>,
+}
+
+impl<'a, T> OrderedRows<'a, T> {
+    fn new(
+        rows: Pin<Box<dyn Stream<Item = Result<T, sqlx::Error>> + Send + 'a>>,
+        match_id: fn(&T) -> i64,
+    ) -> Self {
+        Self {
+            rows,
+            match_id,
+            ahead: None,
+        }
+    }
+
+    /// rows of the match `id`, the remaining rows of the previous matches are skipped
+    async fn take(&mut self, id: i64) -> Vec<T> {
+        let mut taken = vec![];
+        loop {
+            let row = match self.ahead.take() {
+                Some(row) => row,
+                None => match self.rows.next().await {
+                    Some(row) => row.expect("Cannot query matches from db"),
+                    None => return taken,
+                },
+            };
+            match (self.match_id)(&row).cmp(&id) {
+                Ordering::Less => {}
+                Ordering::Equal => taken.push(row),
+                Ordering::Greater => {
+                    self.ahead = Some(row);
+                    return taken;
+                }
+            }
+        }
     }
 }
@@ -7,16 +7,35 @@ pub struct Bot {
     pub source_code: SourceCode,
     pub language: Language,
     pub created_at: DateTime<Utc>,
+    /// archived bots are kept in the leaderboard but are not matchmade
+    pub archived: bool,
+    /// set when the arena stopped matchmaking the bot by itself, unarchiving resumes it
+    pub paused_reason: Option<String>,
+    /// user who submitted the bot
+    pub author: Option<String>,
 }
 
 impl Bot {
-    pub fn new(name: BotName, source_code: SourceCode, language: Language) -> Self {
+    /// neither archived by the user nor paused by the arena
+    pub fn is_matchmade(&self) -> bool {
+        !self.archived && self.paused_reason.is_none()
+    }
+
+    pub fn new(
+        name: BotName,
+        source_code: SourceCode,
+        language: Language,
+        author: Option<String>,
+    ) -> Self {
         Self {
             id: BotId::UNINITIALIZED,
             name,
             source_code,
             language,
             created_at: Utc::now(),
+            archived: false,
+            paused_reason: None,
+            author,
         }
     }
 }
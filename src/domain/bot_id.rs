@@ -1,4 +1,7 @@
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct BotId(i64);
 
 impl BotId {
@@ -44,6 +44,13 @@ impl Build {
         matches!(self.status, BuildStatus::Running)
     }
 
+    pub fn has_failed(&self) -> bool {
+        matches!(
+            self.status,
+            BuildStatus::Finished(BuildResult::Failure { .. })
+        )
+    }
+
     pub fn was_finished_successfully(&self) -> bool {
         matches!(self.status, BuildStatus::Finished(BuildResult::Success))
     }
@@ -1,16 +1,92 @@
 use crate::domain::{BotId, MatchId};
+use anyhow::bail;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 // only successfully finished matches would be stored in DB
 pub struct Match {
     pub id: MatchId,
     pub seed: i64,
     pub participants: Vec<Participant>,
+    /// matches of the same group are seat permutations of a single seed
+    pub group_id: Option<i64>,
+    /// values of the game parameters the match was played with
+    pub params: BTreeMap<String, String>,
+    pub created_at: DateTime<Utc>,
 }
 
 pub struct Participant {
     pub bot_id: BotId,
+    /// rank of the participant's team
     pub rank: u8,
     pub error: bool,
+    /// kind of the error if the referee classified it
+    pub error_kind: Option<ErrorKind>,
+    pub error_message: Option<String>,
+    /// raw score of the participant's team if the referee reports it
+    pub score: Option<f64>,
+    /// custom values reported by the referee, e.g. turns survived
+    pub metrics: BTreeMap<String, f64>,
+    /// index of the team in the match, every bot is a team of its own in non-team games
+    pub team: u8,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Timeout,
+    InvalidOutput,
+    Crash,
+    Other,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::InvalidOutput => "invalid_output",
+            ErrorKind::Crash => "crash",
+            ErrorKind::Other => "other",
+        }
+    }
+}
+
+impl TryFrom<&str> for ErrorKind {
+    type Error = anyhow::Error;
+
+    fn try_from(src: &str) -> Result<Self, Self::Error> {
+        match src {
+            "timeout" => Ok(ErrorKind::Timeout),
+            "invalid_output" => Ok(ErrorKind::InvalidOutput),
+            "crash" => Ok(ErrorKind::Crash),
+            "other" => Ok(ErrorKind::Other),
+            _ => bail!("Unknown error kind '{src}'"),
+        }
+    }
+}
+
+impl Participant {
+    pub fn is_teammate(&self, other: &Participant) -> bool {
+        self.team == other.team
+    }
+}
+
+#[cfg(test)]
+impl Participant {
+    /// participant without errors, scores and metrics
+    pub fn ranked(bot_id: BotId, team: u8, rank: u8) -> Self {
+        Participant {
+            bot_id,
+            rank,
+            error: false,
+            error_kind: None,
+            error_message: None,
+            score: None,
+            metrics: BTreeMap::new(),
+            team,
+        }
+    }
 }
 
 impl Match {
@@ -19,6 +95,9 @@ impl Match {
             id: MatchId::UNINITIALIZED,
             seed,
             participants,
+            group_id: None,
+            params: BTreeMap::new(),
+            created_at: Utc::now(),
         }
     }
 }
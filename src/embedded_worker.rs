@@ -1,9 +1,13 @@
-use crate::config::EmbeddedWorkerConfig;
-use crate::domain::{BotId, BuildResult, Language, Participant, SourceCode, WorkerName};
+use crate::config::{param_placeholder, EmbeddedWorkerConfig};
+use crate::domain::{BotId, BuildResult, ErrorKind, Language, Participant, SourceCode, WorkerName};
+use crate::metrics::GameMetrics;
+use anyhow::{bail, Context};
 use itertools::Itertools;
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 use tokio::sync::Semaphore;
 use tokio::{fs, process::Command};
@@ -12,15 +16,44 @@ use tracing::warn;
 
 pub struct EmbeddedWorker {
     worker_path: PathBuf,
+    /// relative to `worker_path`, the commands get the bot directories relative to it
+    bots_dir: PathBuf,
     pub config: Arc<EmbeddedWorkerConfig>,
     pub match_tx: Sender<PlayMatchInput>,
     pub match_result_rx: Receiver<PlayMatchOutput>,
+    metrics: Arc<GameMetrics>,
 }
 
 const DIR_BOTS: &str = "bots";
 
 impl EmbeddedWorker {
-    pub fn new(worker_path: &Path, config: EmbeddedWorkerConfig, token: CancellationToken) -> Self {
+    /// `pool` limits how many matches are run in parallel by all the workers sharing it
+    pub fn new(
+        worker_path: &Path,
+        config: EmbeddedWorkerConfig,
+        pool: Arc<Semaphore>,
+        metrics: Arc<GameMetrics>,
+        token: CancellationToken,
+    ) -> Self {
+        Self::with_bots_dir(
+            worker_path,
+            Path::new(DIR_BOTS),
+            config,
+            pool,
+            metrics,
+            token,
+        )
+    }
+
+    /// worker keeping the bots apart from the arena ones, in `bots_dir` relative to `worker_path`
+    pub fn with_bots_dir(
+        worker_path: &Path,
+        bots_dir: &Path,
+        config: EmbeddedWorkerConfig,
+        pool: Arc<Semaphore>,
+        metrics: Arc<GameMetrics>,
+        token: CancellationToken,
+    ) -> Self {
         let config = Arc::new(config);
 
         let (match_result_tx, match_result_rx) = channel(100);
@@ -28,16 +61,21 @@ impl EmbeddedWorker {
         tokio::spawn(run_play_matches(
             match_rx,
             worker_path.to_path_buf(),
+            bots_dir.to_path_buf(),
             Arc::clone(&config),
+            pool,
+            Arc::clone(&metrics),
             match_result_tx,
             token.clone(),
         ));
 
         Self {
             worker_path: worker_path.to_path_buf(),
+            bots_dir: bots_dir.to_path_buf(),
             config,
             match_tx,
             match_result_rx,
+            metrics,
         }
     }
 
@@ -45,7 +83,15 @@ impl EmbeddedWorker {
         let bot_id = input.bot_id;
         let worker_name = input.worker_name.clone();
 
-        let result = build_bot(self.worker_path.clone(), Arc::clone(&self.config), input).await;
+        let started = Instant::now();
+        let result = build_bot(
+            self.worker_path.clone(),
+            &self.bots_dir,
+            Arc::clone(&self.config),
+            input,
+        )
+        .await;
+        self.metrics.build_duration.observe(started.elapsed());
 
         BuildBotOutput {
             bot_id,
@@ -55,20 +101,25 @@ impl EmbeddedWorker {
     }
 
     pub async fn is_build_valid(&self, id: BotId) -> bool {
-        let bot_folder = self
-            .worker_path
-            .join(DIR_BOTS)
-            .join(i64::from(id).to_string());
-        tokio::fs::try_exists(&bot_folder).await.unwrap_or(false)
+        tokio::fs::try_exists(self.bot_dir(id))
+            .await
+            .unwrap_or(false)
+    }
+
+    pub fn bot_dir(&self, id: BotId) -> PathBuf {
+        self.worker_path
+            .join(&self.bots_dir)
+            .join(i64::from(id).to_string())
     }
 }
 
 async fn build_bot(
     worker_path: PathBuf,
+    bots_dir: &Path,
     config: Arc<EmbeddedWorkerConfig>,
     input: BuildBotInput,
 ) -> BuildResult {
-    let bot_folder_relative = PathBuf::from(DIR_BOTS).join(i64::from(input.bot_id).to_string());
+    let bot_folder_relative = bots_dir.join(i64::from(input.bot_id).to_string());
     let bot_folder = worker_path.join(&bot_folder_relative);
     if bot_folder.exists() {
         warn!("bot folder already exists, skipping build");
@@ -113,10 +164,14 @@ async fn build_bot(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run_play_matches(
     mut rx: Receiver<PlayMatchInput>,
     worker_path: PathBuf,
+    bots_dir: PathBuf,
     config: Arc<EmbeddedWorkerConfig>,
+    pool: Arc<Semaphore>,
+    metrics: Arc<GameMetrics>,
     match_result_tx: Sender<PlayMatchOutput>,
     token: CancellationToken,
 ) {
@@ -129,12 +184,17 @@ async fn run_play_matches(
 
         let semaphore = Arc::clone(&semaphore);
         let permit = semaphore.acquire_owned().await.expect("Semaphore poisoned");
+        // semaphore is fair, so the workers sharing the pool take turns
+        let pool_permit = Arc::clone(&pool)
+            .acquire_owned()
+            .await
+            .expect("Semaphore poisoned");
+        metrics.worker_permits_in_use.inc();
         let run_commands = input
             .bots
             .iter()
             .map(|b| {
-                let bot_folder_relative =
-                    PathBuf::from(DIR_BOTS).join(i64::from(b.bot_id).to_string());
+                let bot_folder_relative = bots_dir.join(i64::from(b.bot_id).to_string());
                 let dir_param_value = bot_folder_relative.to_str().unwrap();
                 config
                     .cmd_run
@@ -160,7 +220,9 @@ async fn run_play_matches(
                 "{P7}" => &run_commands[6],
                 "{P8}" => &run_commands[7],
                 "{PLAYERS}" => &run_commands_combined,
-                _ => s,
+                _ => param_placeholder(s)
+                    .and_then(|name| input.params.get(name))
+                    .map_or(s, |v| v),
             })
             .map(|s| s.to_string())
             .collect_vec();
@@ -168,7 +230,9 @@ async fn run_play_matches(
 
         let match_result_tx_clone = match_result_tx.clone();
         let worker_path_clone = worker_path.clone();
+        let metrics = Arc::clone(&metrics);
         tokio::spawn(async move {
+            let started = Instant::now();
             spawn_play_match_command(
                 command_parts,
                 worker_path_clone,
@@ -176,6 +240,9 @@ async fn run_play_matches(
                 match_result_tx_clone,
             )
             .await;
+            metrics.match_duration.observe(started.elapsed());
+            metrics.worker_permits_in_use.dec();
+            drop(pool_permit);
             drop(permit);
         });
     }
@@ -187,38 +254,16 @@ async fn spawn_play_match_command(
     input: PlayMatchInput,
     match_result_tx: Sender<PlayMatchOutput>,
 ) {
-    let cmd_output = Command::new(&command_parts[0])
-        .args(&command_parts[1..])
-        .current_dir(&worker_path)
-        .output()
-        .await
-        .expect("Cannot run match");
-
-    let result = if cmd_output.status.success() {
-        let stdout = String::from_utf8(cmd_output.stdout).expect("stdout is not valid UTF-8");
-        let match_result: CmdPlayMatchStdout =
-            serde_json::from_str(&stdout).expect("play match output should be valid JSON");
-        match_result
-    } else {
-        panic!(
-            "Error while running match: {}",
-            String::from_utf8(cmd_output.stderr).expect("stderr is not valid UTF-8")
-        );
-    };
+    let result = run_play_match_command(&command_parts, &worker_path, &input).await;
+    if let Err(e) = &result {
+        warn!("Match with seed {} failed: {:#}", input.seed, e);
+    }
 
     let output = PlayMatchOutput {
         seed: input.seed,
-        participants: input
-            .bots
-            .iter()
-            .zip_eq(result.ranks)
-            .zip_eq(result.errors)
-            .map(|((b, r), e)| Participant {
-                bot_id: b.bot_id,
-                rank: r,
-                error: e == 1,
-            })
-            .collect(),
+        group_id: input.group_id,
+        params: input.params,
+        result,
     };
 
     match_result_tx
@@ -227,6 +272,61 @@ async fn spawn_play_match_command(
         .expect("Cannot send match result");
 }
 
+async fn run_play_match_command(
+    command_parts: &[String],
+    worker_path: &Path,
+    input: &PlayMatchInput,
+) -> Result<Vec<Participant>, anyhow::Error> {
+    let cmd_output = Command::new(&command_parts[0])
+        .args(&command_parts[1..])
+        .current_dir(worker_path)
+        .output()
+        .await
+        .context("Cannot run match")?;
+
+    if !cmd_output.status.success() {
+        bail!(
+            "Error while running match: {}",
+            String::from_utf8_lossy(&cmd_output.stderr)
+        );
+    }
+
+    let stdout = String::from_utf8(cmd_output.stdout).context("stdout is not valid UTF-8")?;
+    let result: CmdPlayMatchStdout =
+        serde_json::from_str(&stdout).context("play match output should be valid JSON")?;
+
+    let n = input.bots.len();
+    let n_teams = input.bots.iter().map(|b| b.team).unique().count();
+    if result.ranks.len() != n_teams
+        || result.errors.len() != n
+        || result.scores.as_ref().is_some_and(|s| s.len() != n_teams)
+        || result.metrics.as_ref().is_some_and(|m| m.len() != n)
+    {
+        bail!(
+            "play match output should contain ranks and scores for {n_teams} teams, errors and metrics for {n} players"
+        );
+    }
+
+    let metrics = result.metrics.unwrap_or_else(|| vec![BTreeMap::new(); n]);
+
+    Ok(input
+        .bots
+        .iter()
+        .zip_eq(result.errors)
+        .zip_eq(metrics)
+        .map(|((b, e), metrics)| Participant {
+            bot_id: b.bot_id,
+            rank: result.ranks[b.team as usize],
+            error: e.is_error(),
+            error_kind: e.kind(),
+            error_message: e.message(),
+            score: result.scores.as_ref().map(|s| s[b.team as usize]),
+            metrics,
+            team: b.team,
+        })
+        .collect())
+}
+
 #[derive(Clone)]
 pub struct BuildBotInput {
     pub bot_id: BotId,
@@ -245,21 +345,85 @@ pub struct BuildBotOutput {
 pub struct PlayMatchInput {
     pub bots: Vec<PlayMatchBot>,
     pub seed: i64,
+    pub group_id: Option<i64>,
+    pub params: BTreeMap<String, String>,
 }
 
 #[derive(Clone)]
 pub struct PlayMatchBot {
     pub bot_id: BotId,
     pub language: Language,
+    /// teams are numbered from 0 in order of their seats
+    pub team: u8,
 }
 
 pub struct PlayMatchOutput {
     pub seed: i64,
-    pub participants: Vec<Participant>,
+    pub group_id: Option<i64>,
+    pub params: BTreeMap<String, String>,
+    /// error if the referee failed to play the match
+    pub result: Result<Vec<Participant>, anyhow::Error>,
 }
 
 #[derive(Deserialize)]
 pub struct CmdPlayMatchStdout {
+    /// one rank per team
     pub ranks: Vec<u8>,
-    pub errors: Vec<u8>,
+    pub errors: Vec<CmdPlayMatchError>,
+    /// one raw score per team, optional
+    #[serde(default)]
+    pub scores: Option<Vec<f64>>,
+    /// custom named values per player, optional
+    #[serde(default)]
+    pub metrics: Option<Vec<BTreeMap<String, f64>>>,
+}
+
+/// either 0/1 flag or the classified error
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum CmdPlayMatchError {
+    Flag(u8),
+    Detailed {
+        kind: CmdPlayMatchErrorKind,
+        #[serde(default)]
+        message: Option<String>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CmdPlayMatchErrorKind {
+    Timeout,
+    InvalidOutput,
+    Crash,
+    #[serde(other)]
+    Other,
+}
+
+impl CmdPlayMatchError {
+    fn is_error(&self) -> bool {
+        match self {
+            CmdPlayMatchError::Flag(flag) => *flag == 1,
+            CmdPlayMatchError::Detailed { .. } => true,
+        }
+    }
+
+    fn kind(&self) -> Option<ErrorKind> {
+        match self {
+            CmdPlayMatchError::Flag(_) => None,
+            CmdPlayMatchError::Detailed { kind, .. } => Some(match kind {
+                CmdPlayMatchErrorKind::Timeout => ErrorKind::Timeout,
+                CmdPlayMatchErrorKind::InvalidOutput => ErrorKind::InvalidOutput,
+                CmdPlayMatchErrorKind::Crash => ErrorKind::Crash,
+                CmdPlayMatchErrorKind::Other => ErrorKind::Other,
+            }),
+        }
+    }
+
+    fn message(&self) -> Option<String> {
+        match self {
+            CmdPlayMatchError::Flag(_) => None,
+            CmdPlayMatchError::Detailed { message, .. } => message.clone(),
+        }
+    }
 }
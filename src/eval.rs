@@ -0,0 +1,488 @@
+use crate::arena::{rating_settings, StatsSnapshot, WinDrawLoss};
+use crate::config::{Config, GameConfig, WorkerConfig};
+use crate::db::Database;
+use crate::domain::{Bot, BotId, BuildResult, Language, Match, Rating, SourceCode, WorkerName};
+use crate::embedded_worker::{BuildBotInput, EmbeddedWorker, PlayMatchBot, PlayMatchInput};
+use crate::ranking::Ranker;
+use anyhow::{bail, Context};
+use clap::ValueEnum;
+use itertools::Itertools;
+use rand::prelude::SliceRandom;
+use rand::{thread_rng, Rng};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Clone, Debug)]
+pub enum Opponents {
+    /// N bots with the highest rating
+    Top(usize),
+    Names(Vec<String>),
+}
+
+impl FromStr for Opponents {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(n) = s.strip_prefix("top:") {
+            let n: usize = n.parse().context("expected 'top:N'")?;
+            if n == 0 {
+                bail!("at least one opponent is required");
+            }
+            return Ok(Opponents::Top(n));
+        }
+        let names = s
+            .split(',')
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .map(str::to_string)
+            .collect_vec();
+        if names.is_empty() {
+            bail!("at least one opponent is required");
+        }
+        Ok(Opponents::Names(names))
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+pub enum ReportFormat {
+    Json,
+    Markdown,
+}
+
+pub struct EvalOptions {
+    pub bot: PathBuf,
+    pub language: String,
+    pub against: Opponents,
+    pub matches: u32,
+    pub threads: Option<u8>,
+    pub format: ReportFormat,
+    pub min_win_rate: Option<f64>,
+    /// default game of the arena if not set
+    pub game: Option<String>,
+}
+
+#[derive(Serialize)]
+struct EvalReport {
+    matches: usize,
+    wins: usize,
+    draws: usize,
+    loses: usize,
+    /// draws count as half a win
+    win_rate: f64,
+    matches_with_error: usize,
+    rating_mu: f64,
+    rating_sigma: f64,
+    rating_score: f64,
+    /// rank of the candidate among the opponents
+    rank: usize,
+    opponents: Vec<OpponentReport>,
+    passed: bool,
+}
+
+#[derive(Serialize)]
+struct OpponentReport {
+    name: String,
+    rating_mu: f64,
+    rating_sigma: f64,
+    wins: usize,
+    draws: usize,
+    loses: usize,
+}
+
+/// Evaluates a candidate bot against the existing arena bots without starting the web server.
+/// Eval matches are not persisted, so the arena leaderboard is not affected.
+///
+/// Returns process exit code: 0 if candidate passed the threshold, 1 if it did not,
+/// 2 if the evaluation could not be performed.
+pub async fn run(arena_path: &Path, options: EvalOptions) -> i32 {
+    match evaluate(arena_path, &options).await {
+        Ok(report) => {
+            let output = match options.format {
+                ReportFormat::Json => {
+                    serde_json::to_string_pretty(&report).expect("report is serializable")
+                }
+                ReportFormat::Markdown => markdown(&report),
+            };
+            println!("{output}");
+            if report.passed {
+                0
+            } else {
+                1
+            }
+        }
+        Err(e) => {
+            eprintln!("Evaluation failed: {e:#}");
+            2
+        }
+    }
+}
+
+async fn evaluate(arena_path: &Path, options: &EvalOptions) -> Result<EvalReport, anyhow::Error> {
+    let config = Config::load(arena_path).context("Cannot load arena config")?;
+    config.validate().context("Invalid config")?;
+    let game = config.find_game(options.game.as_deref())?;
+    let game_path = arena_path.join(&game.dir);
+
+    let [WorkerConfig::Embedded(worker_config)] = game.workers.as_slice() else {
+        bail!("In the current version only single embedded worker supported");
+    };
+    let mut worker_config = worker_config.clone();
+    if let Some(threads) = options.threads {
+        worker_config.threads = threads;
+    }
+
+    let source_code: SourceCode = tokio::fs::read_to_string(&options.bot)
+        .await
+        .with_context(|| format!("Cannot read {}", options.bot.display()))?
+        .try_into()?;
+    let language: Language = options.language.clone().try_into()?;
+
+    let ranker = Ranker::new(game.ranking.clone());
+    // the arena may be running, it owns the database and the migrations
+    let db = Database::connect_read_only(&game_path).await?;
+    let bots = db.fetch_bots().await;
+    let settings = rating_settings(&ranker, game.game.permutation_rating);
+    let snapshot = StatsSnapshot::load(&db, &settings).await?;
+    // only the matches after the snapshot are replayed
+    let matches = db
+        .fetch_matches(snapshot.as_ref().map(|s| s.last_match_id()))
+        .await;
+
+    // ratings of the pruned matches are only stored in the snapshot
+    let mut ratings = snapshot
+        .as_ref()
+        .map(|s| s.ratings().clone())
+        .unwrap_or_default();
+    ranker.recalc_rating(&mut ratings, matches.iter());
+    let rating = |ratings: &HashMap<BotId, Rating>, id: BotId| {
+        ratings
+            .get(&id)
+            .copied()
+            .unwrap_or_else(|| ranker.default_rating())
+    };
+
+    let opponents: Vec<&Bot> = match &options.against {
+        Opponents::Top(n) => bots
+            .iter()
+            .filter(|b| b.is_matchmade())
+            .sorted_by(|a, b| {
+                rating(&ratings, b.id)
+                    .score()
+                    .total_cmp(&rating(&ratings, a.id).score())
+            })
+            .take(*n)
+            .collect(),
+        Opponents::Names(names) => names
+            .iter()
+            .map(|name| {
+                bots.iter()
+                    .find(|b| &*b.name == name)
+                    .with_context(|| format!("Bot '{name}' not found"))
+            })
+            .try_collect()?,
+    };
+
+    let team_size = game.game.team_size as usize;
+    let max_players = ((opponents.len() + 1) / team_size).min(game.game.max_players as usize);
+    if max_players < game.game.min_players as usize {
+        bail!(
+            "At least {} opponents are required",
+            game.game.min_players as usize * team_size - 1
+        );
+    }
+
+    let token = CancellationToken::new();
+    let pool = Arc::new(Semaphore::new(worker_config.threads as usize));
+    // the arena may be running, so its bot directories are neither reused nor touched
+    let bots_dir = PathBuf::from(format!("eval-{}", std::process::id()));
+    let mut worker = EmbeddedWorker::with_bots_dir(
+        &game_path,
+        &bots_dir,
+        worker_config,
+        pool,
+        Default::default(),
+        token.clone(),
+    );
+
+    // candidate is never persisted, negative id guarantees no clash with the opponents
+    let candidate_id = BotId::from(-i64::from(std::process::id()));
+    let candidate_build = build(&worker, candidate_id, source_code, language.clone()).await;
+    let result = async {
+        if let BuildResult::Failure { stderr } = candidate_build {
+            bail!("Candidate build failed:\n{stderr}");
+        }
+        for opponent in &opponents {
+            let result = build(
+                &worker,
+                opponent.id,
+                opponent.source_code.clone(),
+                opponent.language.clone(),
+            )
+            .await;
+            if let BuildResult::Failure { stderr } = result {
+                bail!("Build of '{}' failed:\n{stderr}", &*opponent.name);
+            }
+        }
+
+        let inputs = schedule_matches(
+            &game.game,
+            game.matchmaking.seeds.as_ref().map(|s| s.list()),
+            options.matches as usize,
+            max_players,
+            PlayMatchBot {
+                bot_id: candidate_id,
+                language,
+                team: 0,
+            },
+            &opponents,
+        );
+        let total = inputs.len();
+
+        let match_tx = worker.match_tx.clone();
+        tokio::spawn(async move {
+            for input in inputs {
+                if match_tx.send(input).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut played = Vec::with_capacity(total);
+        let mut failed = 0;
+        while played.len() + failed < total {
+            let output = worker
+                .match_result_rx
+                .recv()
+                .await
+                .context("Worker stopped unexpectedly")?;
+            let participants = match output.result {
+                Ok(participants) => participants,
+                Err(e) => {
+                    eprintln!("Match with seed {} failed: {e:#}", output.seed);
+                    failed += 1;
+                    continue;
+                }
+            };
+            let m = Match::new(output.seed, participants);
+            ranker.recalc_rating(&mut ratings, std::iter::once(&m));
+            played.push(m);
+            if played.len() % (total / 10).max(1) == 0 {
+                eprintln!("Played {}/{} matches", played.len(), total);
+            }
+        }
+        if played.is_empty() {
+            bail!("All the matches failed");
+        }
+        Ok(played)
+    }
+    .await;
+
+    token.cancel();
+    let _ = tokio::fs::remove_dir_all(game_path.join(&bots_dir)).await;
+    let played = result?;
+
+    let mut total = WinDrawLoss::default();
+    let mut per_opponent: HashMap<BotId, WinDrawLoss> = HashMap::new();
+    let mut matches_with_error = 0;
+    for m in &played {
+        let candidate = m
+            .participants
+            .iter()
+            .find(|p| p.bot_id == candidate_id)
+            .expect("candidate plays every eval match");
+        if candidate.error {
+            matches_with_error += 1;
+        }
+
+        let best_other_rank = m
+            .participants
+            .iter()
+            .filter(|p| !candidate.is_teammate(p))
+            .map(|p| p.rank)
+            .min()
+            .expect("eval match has at least one opponent");
+        total.record(candidate.rank.cmp(&best_other_rank));
+
+        for p in m.participants.iter().filter(|p| !candidate.is_teammate(p)) {
+            per_opponent
+                .entry(p.bot_id)
+                .or_default()
+                .record(candidate.rank.cmp(&p.rank));
+        }
+    }
+
+    let candidate_rating = rating(&ratings, candidate_id);
+    let win_rate = total.win_rate().unwrap_or_default();
+    let rank = 1 + opponents
+        .iter()
+        .filter(|o| rating(&ratings, o.id).score() > candidate_rating.score())
+        .count();
+
+    Ok(EvalReport {
+        matches: played.len(),
+        wins: total.wins,
+        draws: total.draws,
+        loses: total.loses,
+        win_rate,
+        matches_with_error,
+        rating_mu: candidate_rating.mu,
+        rating_sigma: candidate_rating.sigma,
+        rating_score: candidate_rating.score(),
+        rank,
+        opponents: opponents
+            .iter()
+            .map(|o| {
+                let r = rating(&ratings, o.id);
+                let wdl = per_opponent.remove(&o.id).unwrap_or_default();
+                OpponentReport {
+                    name: o.name.to_string(),
+                    rating_mu: r.mu,
+                    rating_sigma: r.sigma,
+                    wins: wdl.wins,
+                    draws: wdl.draws,
+                    loses: wdl.loses,
+                }
+            })
+            .collect(),
+        passed: options.min_win_rate.is_none_or(|min| win_rate >= min),
+    })
+}
+
+async fn build(
+    worker: &EmbeddedWorker,
+    bot_id: BotId,
+    source_code: SourceCode,
+    language: Language,
+) -> BuildResult {
+    let input = BuildBotInput {
+        bot_id,
+        worker_name: WorkerName::embedded(),
+        source_code,
+        language,
+    };
+    worker.build_bot(input).await.result
+}
+
+fn schedule_matches(
+    game: &GameConfig,
+    seeds: Option<&[i64]>,
+    matches: usize,
+    max_players: usize,
+    candidate: PlayMatchBot,
+    opponents: &[&Bot],
+) -> Vec<PlayMatchInput> {
+    let mut rng = thread_rng();
+    let mut inputs = Vec::with_capacity(matches);
+
+    while inputs.len() < matches {
+        let n_teams = rng.gen_range(game.min_players as usize..=max_players);
+        let mut bots = opponents
+            .choose_multiple(&mut rng, n_teams * game.team_size as usize - 1)
+            .map(|b| PlayMatchBot {
+                bot_id: b.id,
+                language: b.language.clone(),
+                team: 0,
+            })
+            .collect_vec();
+        bots.push(candidate.clone());
+        bots.shuffle(&mut rng);
+        let teams = bots
+            .chunks(game.team_size as usize)
+            .map(|team| team.to_vec())
+            .collect_vec();
+
+        let seed = match seeds {
+            Some(seeds) => *seeds
+                .choose(&mut rng)
+                .expect("seeds are validated to be non-empty"),
+            None => rng.gen(),
+        };
+        let params = game.sample_params(&mut rng);
+        let seatings = if game.symmetric {
+            vec![teams]
+        } else {
+            teams.into_iter().permutations(n_teams).collect()
+        };
+        inputs.extend(seatings.into_iter().map(|teams| {
+            PlayMatchInput {
+                seed,
+                bots: teams
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(|(team, bots)| {
+                        bots.into_iter().map(move |b| PlayMatchBot {
+                            team: team as u8,
+                            ..b
+                        })
+                    })
+                    .collect(),
+                group_id: None,
+                params: params.clone(),
+            }
+        }));
+    }
+    inputs.truncate(matches);
+    inputs
+}
+
+fn markdown(report: &EvalReport) -> String {
+    let mut s = String::new();
+    let _ = writeln!(
+        s,
+        "## Evaluation {}",
+        if report.passed { "passed" } else { "failed" }
+    );
+    let _ = writeln!(s);
+    let _ = writeln!(
+        s,
+        "| Matches | Wins | Draws | Loses | Win rate | Errors | Rating | Rank |"
+    );
+    let _ = writeln!(s, "|---|---|---|---|---|---|---|---|");
+    let _ = writeln!(
+        s,
+        "| {} | {} | {} | {} | {:.1}% | {} | {:.2} ({:.2} ± {:.2}) | {} |",
+        report.matches,
+        report.wins,
+        report.draws,
+        report.loses,
+        report.win_rate * 100.0,
+        report.matches_with_error,
+        report.rating_score,
+        report.rating_mu,
+        report.rating_sigma,
+        report.rank,
+    );
+    let _ = writeln!(s);
+    let _ = writeln!(s, "| Opponent | Rating | Wins | Draws | Loses |");
+    let _ = writeln!(s, "|---|---|---|---|---|");
+    for o in &report.opponents {
+        let _ = writeln!(
+            s,
+            "| {} | {:.2} ± {:.2} | {} | {} | {} |",
+            o.name, o.rating_mu, o.rating_sigma, o.wins, o.draws, o.loses
+        );
+    }
+    s
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn opponents_can_be_parsed() {
+        assert!(matches!("top:3".parse(), Ok(Opponents::Top(3))));
+        assert!(matches!(
+            "a, b".parse(),
+            Ok(Opponents::Names(names)) if names == ["a", "b"]
+        ));
+        assert!("top:0".parse::<Opponents>().is_err());
+        assert!(",".parse::<Opponents>().is_err());
+    }
+}
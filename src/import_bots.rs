@@ -0,0 +1,251 @@
+use crate::arena::CreateBotResult;
+use crate::client::{local_arena_url, ArenaClient};
+use crate::domain::{BotName, Language, SourceCode};
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// optional file in the imported directory listing the bots explicitly
+const MANIFEST_FILE_NAME: &str = "bots.toml";
+
+pub struct ImportBotsOptions {
+    pub dir: PathBuf,
+    /// default game of the arena if not set
+    pub game: Option<String>,
+    /// API token of a user, required when the arena has users configured
+    pub token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    bots: Vec<ManifestBot>,
+}
+
+#[derive(Deserialize)]
+struct ManifestBot {
+    /// path relative to the imported directory
+    file: PathBuf,
+    /// file name without extension by default
+    name: Option<String>,
+    language: String,
+}
+
+/// Bot to submit, `file` is relative to the imported directory.
+#[derive(Debug, PartialEq)]
+struct BotFile {
+    file: PathBuf,
+    name: String,
+    language: String,
+}
+
+/// Submits every bot of the directory to the running arena, returns the exit code:
+/// non-zero if any of the bots could not be submitted.
+pub async fn run(arena_path: &Path, url: Option<String>, options: ImportBotsOptions) -> i32 {
+    match import(arena_path, url, options).await {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("Cannot import bots: {e:#}");
+            1
+        }
+    }
+}
+
+/// `Ok(false)` if some bots failed
+async fn import(
+    arena_path: &Path,
+    url: Option<String>,
+    options: ImportBotsOptions,
+) -> Result<bool, anyhow::Error> {
+    let (url, tls_cert) = match url {
+        Some(url) => (url, None),
+        None => local_arena_url(arena_path)?,
+    };
+    let client = ArenaClient::new(
+        &url,
+        options.game.as_deref(),
+        options.token.as_deref(),
+        tls_cert.as_deref(),
+    )?;
+
+    let bots = list_bots(&options.dir)?;
+    let (mut created, mut skipped, mut failed) = (0, 0, 0);
+    for bot in bots {
+        let path = options.dir.join(&bot.file);
+        match submit(&client, &path, &bot).await {
+            Ok(CreateBotResult::Created(_)) => {
+                println!(
+                    "Imported {} as '{}' ({})",
+                    bot.file.display(),
+                    bot.name,
+                    bot.language
+                );
+                created += 1;
+            }
+            Ok(CreateBotResult::DuplicateName) => {
+                println!(
+                    "Skipped {}: '{}' already exists",
+                    bot.file.display(),
+                    bot.name
+                );
+                skipped += 1;
+            }
+            Ok(CreateBotResult::QuotaExceeded) => {
+                eprintln!(
+                    "Failed {}: active bots limit is reached",
+                    bot.file.display()
+                );
+                failed += 1;
+            }
+            Err(e) => {
+                eprintln!("Failed {}: {e:#}", bot.file.display());
+                failed += 1;
+            }
+        }
+    }
+
+    println!("Imported {created} bots, skipped {skipped} existing, failed {failed}");
+    Ok(failed == 0)
+}
+
+async fn submit(
+    client: &ArenaClient,
+    path: &Path,
+    bot: &BotFile,
+) -> Result<CreateBotResult, anyhow::Error> {
+    let name = BotName::try_from(bot.name.clone())?;
+    let language = Language::try_from(bot.language.clone())?;
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Cannot read {}", path.display()))?;
+    let source_code = SourceCode::try_from(content)?;
+    client.create_bot(&name, &source_code, &language).await
+}
+
+/// bots of the manifest if the directory has one, otherwise every `name.lang.ext` file
+fn list_bots(dir: &Path) -> Result<Vec<BotFile>, anyhow::Error> {
+    let manifest_path = dir.join(MANIFEST_FILE_NAME);
+    if manifest_path.exists() {
+        let content = std::fs::read_to_string(&manifest_path)?;
+        let manifest: Manifest = toml::from_str(&content)
+            .with_context(|| format!("Invalid {}", manifest_path.display()))?;
+        return manifest
+            .bots
+            .into_iter()
+            .map(|b| {
+                let name = match b.name {
+                    Some(name) => name,
+                    None => b
+                        .file
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .with_context(|| format!("Cannot get bot name of {}", b.file.display()))?
+                        .to_string(),
+                };
+                Ok(BotFile {
+                    file: b.file,
+                    name,
+                    language: b.language,
+                })
+            })
+            .collect();
+    }
+
+    let mut bots = Vec::new();
+    let mut entries = std::fs::read_dir(dir)
+        .with_context(|| format!("Cannot read {}", dir.display()))?
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let file_name = entry.file_name();
+        let Some(file_name) = file_name.to_str() else {
+            continue;
+        };
+        match parse_file_name(file_name) {
+            Some((name, language)) => bots.push(BotFile {
+                file: PathBuf::from(file_name),
+                name: name.to_string(),
+                language: language.to_string(),
+            }),
+            None => println!("Skipped {file_name}: expected name.lang.ext file name"),
+        }
+    }
+    if bots.is_empty() {
+        bail!(
+            "No bots found in {}, name files as name.lang.ext or list them in {MANIFEST_FILE_NAME}",
+            dir.display()
+        );
+    }
+    Ok(bots)
+}
+
+/// `name.lang.ext` to name and language, the name may contain dots itself
+fn parse_file_name(file_name: &str) -> Option<(&str, &str)> {
+    let (rest, _ext) = file_name.rsplit_once('.')?;
+    let (name, language) = rest.rsplit_once('.')?;
+    if name.is_empty() || language.is_empty() {
+        return None;
+    }
+    Some((name, language))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bots_are_listed_by_file_name_convention() {
+        assert_eq!(parse_file_name("v1.2.c++.cpp"), Some(("v1.2", "c++")));
+        assert_eq!(parse_file_name("bot.py"), None);
+        assert_eq!(parse_file_name(".python.py"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("b.python.py"), "print()").unwrap();
+        std::fs::write(dir.path().join("a.rust.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+        let bots = list_bots(dir.path()).unwrap();
+        assert_eq!(
+            bots,
+            [
+                BotFile {
+                    file: "a.rust.rs".into(),
+                    name: "a".to_string(),
+                    language: "rust".to_string(),
+                },
+                BotFile {
+                    file: "b.python.py".into(),
+                    name: "b".to_string(),
+                    language: "python".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn manifest_takes_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("b.python.py"), "print()").unwrap();
+        std::fs::write(
+            dir.path().join(MANIFEST_FILE_NAME),
+            indoc::indoc! {r#"
+                [[bots]]
+                file = "main.cpp"
+                language = "c++"
+
+                [[bots]]
+                file = "old/main.cpp"
+                name = "old"
+                language = "c++"
+            "#},
+        )
+        .unwrap();
+        let bots = list_bots(dir.path()).unwrap();
+        assert_eq!(bots.len(), 2);
+        assert_eq!(bots[0].name, "main");
+        assert_eq!(bots[1].name, "old");
+        assert_eq!(bots[1].file, Path::new("old/main.cpp"));
+    }
+}
@@ -1,14 +1,26 @@
 mod api;
 mod arena;
 mod arena_server;
+mod backup;
+mod client;
 mod config;
 mod db;
 mod domain;
 mod embedded_worker;
+mod eval;
+mod import_bots;
+mod metrics;
+mod notifier;
 mod ranking;
+mod sprt;
+mod watch;
 
-use clap::{command, Parser, Subcommand};
+use clap::{Parser, Subcommand};
+use eval::{EvalOptions, Opponents, ReportFormat};
+use import_bots::ImportBotsOptions;
 use std::path::PathBuf;
+use std::time::Duration;
+use watch::{PreviousVersion, WatchOptions};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)] // Read from `Cargo.toml`
@@ -25,6 +37,9 @@ enum Commands {
         /// Path to the arena directory. Path would be created if it does not exist.
         /// If omitted the current working directory is used.
         path: Option<String>,
+        /// Generate a self-signed certificate and serve HTTPS
+        #[arg(long)]
+        tls: bool,
     },
     /// Run existing arena
     Run {
@@ -32,20 +47,255 @@ enum Commands {
         /// If omitted the current working directory is used.
         path: Option<String>,
     },
+    /// Evaluate a bot against the arena bots without running the web server.
+    /// Exits with non-zero code if the bot is below the threshold.
+    Eval {
+        /// Path to the bot source file
+        #[arg(long)]
+        bot: PathBuf,
+        /// Language of the bot
+        #[arg(long)]
+        lang: String,
+        /// Opponents: comma separated bot names or 'top:N' for N highest rated bots
+        #[arg(long)]
+        against: Opponents,
+        /// Amount of matches to play
+        #[arg(long, default_value_t = 100)]
+        matches: u32,
+        /// How many matches can be run in parallel.
+        /// If omitted 'threads' from the worker config is used.
+        #[arg(long)]
+        threads: Option<u8>,
+        /// Report format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Json)]
+        format: ReportFormat,
+        /// Minimal win rate (draws count as half a win) for the bot to pass, in 0..1 range
+        #[arg(long)]
+        min_win_rate: Option<f64>,
+        /// Name of the game to evaluate in.
+        /// If omitted the first game from the arena config is used.
+        #[arg(long)]
+        game: Option<String>,
+        /// Path to the arena directory.
+        /// If omitted the current working directory is used.
+        #[arg(long)]
+        arena: Option<String>,
+    },
+    /// Watch bot source file and submit every new version to the running arena
+    Watch {
+        /// Path to the bot source file
+        file: PathBuf,
+        /// Prefix of the submitted bot names, short content hash is appended to it
+        #[arg(long)]
+        name_prefix: String,
+        /// Language of the bot
+        #[arg(long)]
+        lang: String,
+        /// What to do with the previously submitted version
+        #[arg(long, value_enum, default_value_t = PreviousVersion::Keep)]
+        previous: PreviousVersion,
+        /// How often the file is checked for changes, in milliseconds
+        #[arg(long, default_value_t = 1000)]
+        interval: u64,
+        /// URL of the running arena.
+        /// If omitted 'server.port' from the arena config is used.
+        #[arg(long)]
+        url: Option<String>,
+        /// Name of the game to submit to.
+        /// If omitted the first game from the arena config is used.
+        #[arg(long)]
+        game: Option<String>,
+        /// API token of an admin user, required when 'server.users' are configured
+        #[arg(long, env = "CGARENA_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+        /// Path to the arena directory.
+        /// If omitted the current working directory is used.
+        #[arg(long)]
+        arena: Option<String>,
+    },
+    /// Submit every bot of a directory to the running arena.
+    /// Files are named 'name.lang.ext' or listed in 'bots.toml' manifest of the directory.
+    ImportBots {
+        /// Directory with the bot source files
+        dir: PathBuf,
+        /// URL of the running arena.
+        /// If omitted 'server.port' from the arena config is used.
+        #[arg(long)]
+        url: Option<String>,
+        /// Name of the game to submit to.
+        /// If omitted the first game from the arena config is used.
+        #[arg(long)]
+        game: Option<String>,
+        /// API token of an admin user, required when 'server.users' are configured
+        #[arg(long, env = "CGARENA_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+        /// Path to the arena directory.
+        /// If omitted the current working directory is used.
+        #[arg(long)]
+        arena: Option<String>,
+    },
+    /// Export bots, builds and matches of a game to a file
+    Export {
+        /// Output file, '.tar.gz' files are compressed, otherwise plain JSON is written
+        #[arg(long)]
+        out: PathBuf,
+        /// Name of the game to export.
+        /// If omitted the first game from the arena config is used.
+        #[arg(long)]
+        game: Option<String>,
+        /// Path to the arena directory.
+        /// If omitted the current working directory is used.
+        #[arg(long)]
+        arena: Option<String>,
+    },
+    /// Database maintenance
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
+    },
+    /// Import bots, builds and matches exported by 'export' into a game, the arena should be stopped.
+    /// Bots already present with the same name and source code are reused.
+    Import {
+        /// File written by 'export'
+        file: PathBuf,
+        /// Name of the game to import into.
+        /// If omitted the first game from the arena config is used.
+        #[arg(long)]
+        game: Option<String>,
+        /// Path to the arena directory.
+        /// If omitted the current working directory is used.
+        #[arg(long)]
+        arena: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbCommands {
+    /// Rebuild the database file to reclaim the space freed by pruning old matches.
+    /// The arena must be stopped while it runs.
+    Vacuum {
+        /// Name of the game to vacuum.
+        /// If omitted the first game from the arena config is used.
+        #[arg(long)]
+        game: Option<String>,
+        /// Path to the arena directory.
+        /// If omitted the current working directory is used.
+        #[arg(long)]
+        arena: Option<String>,
+    },
 }
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
     match cli.command {
-        Commands::Init { path } => {
+        Commands::Init { path, tls } => {
             let path = unwrap_or_current_dir(path);
-            arena_server::init(&path);
+            arena_server::init(&path, tls);
         }
         Commands::Run { path } => {
             let path = unwrap_or_current_dir(path);
             arena_server::start(&path).await;
         }
+        Commands::Eval {
+            bot,
+            lang,
+            against,
+            matches,
+            threads,
+            format,
+            min_win_rate,
+            game,
+            arena,
+        } => {
+            let path = unwrap_or_current_dir(arena);
+            let options = EvalOptions {
+                bot,
+                language: lang,
+                against,
+                matches,
+                threads,
+                format,
+                min_win_rate,
+                game,
+            };
+            let code = eval::run(&path, options).await;
+            std::process::exit(code);
+        }
+        Commands::Watch {
+            file,
+            name_prefix,
+            lang,
+            previous,
+            interval,
+            url,
+            game,
+            token,
+            arena,
+        } => {
+            let path = unwrap_or_current_dir(arena);
+            let options = WatchOptions {
+                file,
+                name_prefix,
+                language: lang,
+                previous,
+                interval: Duration::from_millis(interval),
+                game,
+                token,
+            };
+            watch::run(&path, url, options).await;
+        }
+        Commands::ImportBots {
+            dir,
+            url,
+            game,
+            token,
+            arena,
+        } => {
+            let path = unwrap_or_current_dir(arena);
+            let options = ImportBotsOptions { dir, game, token };
+            let code = import_bots::run(&path, url, options).await;
+            std::process::exit(code);
+        }
+        Commands::Export { out, game, arena } => {
+            let path = unwrap_or_current_dir(arena);
+            if let Err(e) = backup::export(&path, &out, game.as_deref()).await {
+                eprintln!("Export failed: {e:#}");
+                std::process::exit(1);
+            }
+        }
+        Commands::Db {
+            command: DbCommands::Vacuum { game, arena },
+        } => {
+            let path = unwrap_or_current_dir(arena);
+            match backup::vacuum(&path, game.as_deref()).await {
+                Ok((before, after)) => println!(
+                    "Database size reduced from {} to {} KiB",
+                    before / 1024,
+                    after / 1024
+                ),
+                Err(e) => {
+                    eprintln!("Vacuum failed: {e:#}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Import { file, game, arena } => {
+            let path = unwrap_or_current_dir(arena);
+            match backup::import(&path, &file, game.as_deref()).await {
+                Ok(summary) => println!(
+                    "Imported {} new bots ({} already present) and {} matches ({} between present bots skipped)",
+                    summary.bots_created,
+                    summary.bots_existing,
+                    summary.matches_imported,
+                    summary.matches_skipped
+                ),
+                Err(e) => {
+                    eprintln!("Import failed: {e:#}");
+                    std::process::exit(1);
+                }
+            }
+        }
     }
 }
 
@@ -0,0 +1,262 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// upper bounds in seconds, fit both command handling and long matches
+const BUCKETS: [f64; 14] = [
+    0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0,
+];
+
+#[derive(Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    pub fn inc_by(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Default)]
+pub struct Gauge(AtomicI64);
+
+impl Gauge {
+    pub fn set(&self, value: i64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    pub fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dec(&self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Default)]
+pub struct Histogram {
+    /// non-cumulative counts per bucket, the last one is `+Inf`
+    buckets: [AtomicU64; BUCKETS.len() + 1],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    pub fn observe(&self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        let index = BUCKETS
+            .iter()
+            .position(|&b| secs <= b)
+            .unwrap_or(BUCKETS.len());
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Metrics of a single game, updated by its arena and worker.
+#[derive(Default)]
+pub struct GameMetrics {
+    pub matches: Counter,
+    /// matches in which the referee failed
+    pub failed_matches: Counter,
+    pub participations: Counter,
+    pub participation_errors: Counter,
+    pub match_queue_length: Gauge,
+    pub matches_in_progress: Gauge,
+    pub worker_permits_in_use: Gauge,
+    pub build_duration: Histogram,
+    pub match_duration: Histogram,
+    pub command_latency: Histogram,
+    /// one iteration of the arena loop besides handling commands
+    pub chores_duration: Histogram,
+}
+
+/// State of a game at the moment of scraping.
+pub struct GameSnapshot<'a> {
+    pub name: &'a str,
+    pub metrics: &'a GameMetrics,
+    /// commands waiting for the arena loop
+    pub command_channel_depth: usize,
+}
+
+enum Value<'a> {
+    Counter(&'a Counter),
+    Gauge(i64),
+    Histogram(&'a Histogram),
+}
+
+/// Prometheus text exposition format
+pub fn render(threads: usize, games: &[GameSnapshot]) -> String {
+    let mut out = String::new();
+    write_family(
+        &mut out,
+        "cgarena_threads",
+        "Matches which can be run in parallel across all the games",
+        "gauge",
+    );
+    let _ = writeln!(out, "cgarena_threads {threads}");
+
+    type Getter = for<'a> fn(&'a GameSnapshot<'a>) -> Value<'a>;
+    let families: [(&str, &str, Getter); 12] = [
+        ("cgarena_matches_total", "Finished matches", |g| {
+            Value::Counter(&g.metrics.matches)
+        }),
+        (
+            "cgarena_failed_matches_total",
+            "Matches in which the referee failed",
+            |g| Value::Counter(&g.metrics.failed_matches),
+        ),
+        (
+            "cgarena_participations_total",
+            "Bots participated in the finished matches",
+            |g| Value::Counter(&g.metrics.participations),
+        ),
+        (
+            "cgarena_participation_errors_total",
+            "Bots failed during the finished matches",
+            |g| Value::Counter(&g.metrics.participation_errors),
+        ),
+        (
+            "cgarena_match_queue_length",
+            "Matches scheduled but not yet sent to the worker",
+            |g| Value::Gauge(g.metrics.match_queue_length.get()),
+        ),
+        (
+            "cgarena_matches_in_progress",
+            "Matches sent to the worker and not finished yet",
+            |g| Value::Gauge(g.metrics.matches_in_progress.get()),
+        ),
+        (
+            "cgarena_worker_permits_in_use",
+            "Matches being played by the worker right now",
+            |g| Value::Gauge(g.metrics.worker_permits_in_use.get()),
+        ),
+        (
+            "cgarena_command_channel_depth",
+            "Commands waiting for the arena loop",
+            |g| Value::Gauge(g.command_channel_depth as i64),
+        ),
+        (
+            "cgarena_build_duration_seconds",
+            "Duration of the bot builds",
+            |g| Value::Histogram(&g.metrics.build_duration),
+        ),
+        (
+            "cgarena_match_duration_seconds",
+            "Duration of the matches",
+            |g| Value::Histogram(&g.metrics.match_duration),
+        ),
+        (
+            "cgarena_command_latency_seconds",
+            "Time the arena loop spent handling a command",
+            |g| Value::Histogram(&g.metrics.command_latency),
+        ),
+        (
+            "cgarena_chores_duration_seconds",
+            "Duration of the arena loop iteration besides handling commands",
+            |g| Value::Histogram(&g.metrics.chores_duration),
+        ),
+    ];
+
+    for (name, help, getter) in families {
+        let mut header_written = false;
+        for game in games {
+            let value = getter(game);
+            if !header_written {
+                let kind = match value {
+                    Value::Counter(_) => "counter",
+                    Value::Gauge(_) => "gauge",
+                    Value::Histogram(_) => "histogram",
+                };
+                write_family(&mut out, name, help, kind);
+                header_written = true;
+            }
+            write_value(&mut out, name, game.name, value);
+        }
+    }
+
+    out
+}
+
+fn write_family(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn write_value(out: &mut String, name: &str, game: &str, value: Value) {
+    let game = game.replace('\\', "\\\\").replace('"', "\\\"");
+    match value {
+        Value::Counter(c) => {
+            let _ = writeln!(out, "{name}{{game=\"{game}\"}} {}", c.get());
+        }
+        Value::Gauge(v) => {
+            let _ = writeln!(out, "{name}{{game=\"{game}\"}} {v}");
+        }
+        Value::Histogram(h) => {
+            let mut cumulative = 0;
+            for (index, bucket) in h.buckets.iter().enumerate() {
+                cumulative += bucket.load(Ordering::Relaxed);
+                let le = BUCKETS
+                    .get(index)
+                    .map_or("+Inf".to_string(), |b| b.to_string());
+                let _ = writeln!(
+                    out,
+                    "{name}_bucket{{game=\"{game}\",le=\"{le}\"}} {cumulative}"
+                );
+            }
+            let sum = h.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+            let count = h.count.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{name}_sum{{game=\"{game}\"}} {sum}");
+            let _ = writeln!(out, "{name}_count{{game=\"{game}\"}} {count}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let metrics = GameMetrics::default();
+        metrics.match_duration.observe(Duration::from_millis(300));
+        metrics.match_duration.observe(Duration::from_secs(1000));
+        metrics.matches.inc_by(2);
+
+        let text = render(
+            4,
+            &[GameSnapshot {
+                name: "spring",
+                metrics: &metrics,
+                command_channel_depth: 1,
+            }],
+        );
+        assert!(text.contains("cgarena_threads 4\n"));
+        assert!(text.contains("cgarena_matches_total{game=\"spring\"} 2\n"));
+        assert!(text.contains("cgarena_command_channel_depth{game=\"spring\"} 1\n"));
+        assert!(
+            text.contains("cgarena_match_duration_seconds_bucket{game=\"spring\",le=\"0.25\"} 0\n")
+        );
+        assert!(
+            text.contains("cgarena_match_duration_seconds_bucket{game=\"spring\",le=\"0.5\"} 1\n")
+        );
+        assert!(
+            text.contains("cgarena_match_duration_seconds_bucket{game=\"spring\",le=\"+Inf\"} 2\n")
+        );
+        assert!(text.contains("cgarena_match_duration_seconds_count{game=\"spring\"} 2\n"));
+    }
+}
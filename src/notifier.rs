@@ -0,0 +1,278 @@
+use crate::config::{NotificationConfig, NotificationEvent};
+use crate::domain::{BotId, BotName};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{channel, error::TrySendError, Receiver, Sender};
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+const QUEUE_SIZE: usize = 256;
+const MAX_ATTEMPTS: u32 = 5;
+/// doubled after every failed attempt
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// JSON payload POSTed to the notification url
+#[derive(Serialize, Clone, Debug)]
+pub struct Notification {
+    pub game: String,
+    pub event: NotificationEvent,
+    pub bot_id: i64,
+    pub bot_name: String,
+    /// human readable description, e.g. for chat relays
+    pub text: String,
+}
+
+/// Checks the configured thresholds and queues deliveries to the background task.
+#[derive(Clone)]
+pub struct Notifier {
+    game: String,
+    targets: Arc<Vec<NotificationConfig>>,
+    tx: Sender<(String, Notification)>,
+}
+
+impl Notifier {
+    /// spawns the background task delivering the notifications
+    pub fn start(targets: Vec<NotificationConfig>, token: CancellationToken) -> Self {
+        Self::start_with_retry_delay(targets, RETRY_DELAY, token)
+    }
+
+    fn start_with_retry_delay(
+        targets: Vec<NotificationConfig>,
+        retry_delay: Duration,
+        token: CancellationToken,
+    ) -> Self {
+        let (tx, rx) = channel(QUEUE_SIZE);
+        tokio::spawn(run(rx, retry_delay, token));
+        Self {
+            game: String::new(),
+            targets: Arc::new(targets),
+            tx,
+        }
+    }
+
+    /// notifier of a single game sharing the background task
+    pub fn for_game(&self, game: &str) -> Self {
+        Self {
+            game: game.to_string(),
+            ..self.clone()
+        }
+    }
+
+    pub fn build_failed(&self, bot_id: BotId, bot_name: &BotName) {
+        let text = format!("Build of bot '{}' failed", &**bot_name);
+        self.notify(
+            NotificationEvent::BuildFailed,
+            |_| true,
+            bot_id,
+            bot_name,
+            &text,
+        );
+    }
+
+    pub fn new_leader(&self, bot_id: BotId, bot_name: &BotName) {
+        let text = format!("Bot '{}' is #1 now", &**bot_name);
+        self.notify(
+            NotificationEvent::NewLeader,
+            |_| true,
+            bot_id,
+            bot_name,
+            &text,
+        );
+    }
+
+    /// notifies the targets whose threshold is between `before` (exclusive) and `now`
+    pub fn matches_played(&self, bot_id: BotId, bot_name: &BotName, before: usize, now: usize) {
+        let text = format!("Bot '{}' played {} matches", &**bot_name, now);
+        self.notify(
+            NotificationEvent::MatchesReached,
+            |t| t.matches.is_some_and(|m| before < m && m <= now),
+            bot_id,
+            bot_name,
+            &text,
+        );
+    }
+
+    /// `played` and `errors` are the counts before and after the latest matches,
+    /// the targets are notified when the error rate rises above their threshold
+    pub fn error_rate(
+        &self,
+        bot_id: BotId,
+        bot_name: &BotName,
+        played: (usize, usize),
+        errors: (usize, usize),
+    ) {
+        let exceeds = |t: &NotificationConfig, played: usize, errors: usize| {
+            played >= t.error_rate_min_matches
+                && played > 0
+                && t.max_error_rate
+                    .is_some_and(|max| errors as f64 / played as f64 > max)
+        };
+        let text = format!(
+            "Bot '{}' failed in {} of {} matches",
+            &**bot_name, errors.1, played.1
+        );
+        self.notify(
+            NotificationEvent::ErrorRateExceeded,
+            |t| !exceeds(t, played.0, errors.0) && exceeds(t, played.1, errors.1),
+            bot_id,
+            bot_name,
+            &text,
+        );
+    }
+
+    fn notify(
+        &self,
+        event: NotificationEvent,
+        filter: impl Fn(&NotificationConfig) -> bool,
+        bot_id: BotId,
+        bot_name: &BotName,
+        text: &str,
+    ) {
+        for target in self
+            .targets
+            .iter()
+            .filter(|t| t.events.contains(&event))
+            .filter(|t| filter(t))
+        {
+            let notification = Notification {
+                game: self.game.clone(),
+                event,
+                bot_id: bot_id.into(),
+                bot_name: bot_name.to_string(),
+                text: text.to_string(),
+            };
+            match self.tx.try_send((target.url.clone(), notification)) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    warn!("Too many pending notifications, dropping '{text}'")
+                }
+                Err(TrySendError::Closed(_)) => {}
+            }
+        }
+    }
+}
+
+async fn run(
+    mut rx: Receiver<(String, Notification)>,
+    retry_delay: Duration,
+    token: CancellationToken,
+) {
+    let http = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("Cannot create HTTP client");
+
+    loop {
+        let item = tokio::select! {
+            _ = token.cancelled() => break,
+            item = rx.recv() => item,
+        };
+        let Some((url, notification)) = item else {
+            break;
+        };
+        // retries of a single target should not delay the rest
+        tokio::spawn(deliver(http.clone(), url, notification, retry_delay));
+    }
+}
+
+async fn deliver(
+    http: reqwest::Client,
+    url: String,
+    notification: Notification,
+    retry_delay: Duration,
+) {
+    let mut delay = retry_delay;
+    for attempt in 1..=MAX_ATTEMPTS {
+        let res = http
+            .post(&url)
+            .json(&notification)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        match res {
+            Ok(_) => return,
+            Err(e) => warn!("Notification to {url} failed (attempt {attempt}/{MAX_ATTEMPTS}): {e}"),
+        }
+        if attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::extract::State;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use std::sync::Mutex;
+
+    type Received = Arc<Mutex<Vec<serde_json::Value>>>;
+
+    /// stand-in for the webhook receiver, fails the first request to check retries
+    async fn start_receiver() -> (String, Received) {
+        async fn receive(
+            State((received, calls)): State<(Received, Arc<Mutex<usize>>)>,
+            Json(body): Json<serde_json::Value>,
+        ) -> StatusCode {
+            let mut calls = calls.lock().unwrap();
+            *calls += 1;
+            if *calls == 1 {
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
+            received.lock().unwrap().push(body);
+            StatusCode::OK
+        }
+
+        let received = Received::default();
+        let router = Router::new()
+            .route("/hook", post(receive))
+            .with_state((Arc::clone(&received), Default::default()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        (url, received)
+    }
+
+    #[tokio::test]
+    async fn notifications_are_delivered_with_retries() {
+        let (url, received) = start_receiver().await;
+        let target = NotificationConfig {
+            url,
+            events: vec![
+                NotificationEvent::BuildFailed,
+                NotificationEvent::MatchesReached,
+            ],
+            matches: Some(100),
+            max_error_rate: None,
+            error_rate_min_matches: 20,
+        };
+        let notifier = Notifier::start_with_retry_delay(
+            vec![target],
+            Duration::from_millis(10),
+            CancellationToken::new(),
+        )
+        .for_game("spring");
+
+        let bot_name = BotName::try_from("bot".to_string()).unwrap();
+        notifier.new_leader(BotId::from(1), &bot_name);
+        notifier.matches_played(BotId::from(1), &bot_name, 98, 99);
+        notifier.matches_played(BotId::from(1), &bot_name, 99, 101);
+
+        for _ in 0..100 {
+            if !received.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0]["game"], "spring");
+        assert_eq!(received[0]["event"], "matches_reached");
+        assert_eq!(received[0]["bot_id"], 1);
+    }
+}
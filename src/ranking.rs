@@ -1,9 +1,10 @@
 use crate::config::RankingConfig;
-use crate::domain::{BotId, Match, Rating};
+use crate::domain::{BotId, Match, Participant, Rating};
 use itertools::Itertools;
 use std::collections::HashMap;
 
 pub struct Ranker {
+    config: RankingConfig,
     algorithm: Box<dyn Algorithm + Sync + Send>,
 }
 
@@ -13,10 +14,15 @@ impl Ranker {
             RankingConfig::OpenSkill => openskill::OpenSkill,
         };
         Self {
+            config,
             algorithm: Box::new(algorithm),
         }
     }
 
+    pub fn config(&self) -> &RankingConfig {
+        &self.config
+    }
+
     pub fn default_rating(&self) -> Rating {
         self.algorithm.default_rating()
     }
@@ -27,35 +33,91 @@ impl Ranker {
         matches: impl Iterator<Item = &'a Match>,
     ) {
         for m in matches {
-            let ps = m
-                .participants
-                .iter()
-                .map(|p| {
-                    (
+            self.recalc_rating_for_teams(ratings, &teams(&m.participants));
+        }
+    }
+
+    /// Rates seat permutations of a single seed as one match,
+    /// teams are ranked by the sum of their ranks over all the permutations.
+    pub fn recalc_rating_combined(&self, ratings: &mut HashMap<BotId, Rating>, group: &[&Match]) {
+        let Some(first) = group.first() else {
+            return;
+        };
+        let mut rank_sums: HashMap<BotId, u32> = HashMap::new();
+        for p in group.iter().flat_map(|m| &m.participants) {
+            *rank_sums.entry(p.bot_id).or_default() += p.rank as u32;
+        }
+
+        // teammates are permuted together, so they have the same rank sum
+        let teams = teams(&first.participants);
+        let sums = teams
+            .iter()
+            .map(|(bots, _)| rank_sums[&bots[0]])
+            .collect_vec();
+        let ranks = teams
+            .into_iter()
+            .zip(&sums)
+            .map(|((bots, _), sum)| {
+                let better_cnt = sums.iter().filter(|s| *s < sum).count();
+                (bots, 1 + better_cnt as u8)
+            })
+            .collect_vec();
+        self.recalc_rating_for_teams(ratings, &ranks);
+    }
+
+    fn recalc_rating_for_teams(
+        &self,
+        ratings: &mut HashMap<BotId, Rating>,
+        teams: &[(Vec<BotId>, u8)],
+    ) {
+        let ts = teams
+            .iter()
+            .map(|(bots, rank)| {
+                let team_ratings = bots
+                    .iter()
+                    .map(|id| {
                         ratings
-                            .get(&p.bot_id)
+                            .get(id)
                             .copied()
-                            .unwrap_or_else(|| self.algorithm.default_rating()),
-                        p.rank,
-                    )
-                })
-                .collect_vec();
-
-            let new_ratings = self.algorithm.recalc_ratings(&ps);
+                            .unwrap_or_else(|| self.algorithm.default_rating())
+                    })
+                    .collect();
+                (team_ratings, *rank)
+            })
+            .collect_vec();
+
+        let new_ratings = self.algorithm.recalc_ratings(&ts);
+
+        teams
+            .iter()
+            .zip_eq(new_ratings)
+            .for_each(|((bots, _), new_team_ratings)| {
+                for (id, new_rating) in bots.iter().zip_eq(new_team_ratings) {
+                    ratings.insert(*id, new_rating);
+                }
+            });
+    }
+}
 
-            m.participants
-                .iter()
-                .zip_eq(new_ratings)
-                .for_each(|(p, new_rating)| {
-                    ratings.insert(p.bot_id, new_rating);
-                });
+/// bots of every team together with the team's rank, in order of the first seat of the team
+fn teams(participants: &[Participant]) -> Vec<(Vec<BotId>, u8)> {
+    let mut teams: Vec<(u8, Vec<BotId>, u8)> = Vec::new();
+    for p in participants {
+        match teams.iter_mut().find(|(team, _, _)| *team == p.team) {
+            Some((_, bots, _)) => bots.push(p.bot_id),
+            None => teams.push((p.team, vec![p.bot_id], p.rank)),
         }
     }
+    teams
+        .into_iter()
+        .map(|(_, bots, rank)| (bots, rank))
+        .collect()
 }
 
 trait Algorithm {
     fn default_rating(&self) -> Rating;
-    fn recalc_ratings(&self, input: &[(Rating, u8)]) -> Vec<Rating>;
+    /// every item of `input` is a team with its rank
+    fn recalc_ratings(&self, input: &[(Vec<Rating>, u8)]) -> Vec<Vec<Rating>>;
 }
 
 mod openskill {
@@ -91,9 +153,11 @@ mod openskill {
             WengLinRating::default().into()
         }
 
-        fn recalc_ratings(&self, input: &[(Rating, u8)]) -> Vec<Rating> {
-            let teams: Vec<Vec<WengLinRating>> =
-                input.iter().map(|w| vec![w.0.into()]).collect_vec();
+        fn recalc_ratings(&self, input: &[(Vec<Rating>, u8)]) -> Vec<Vec<Rating>> {
+            let teams: Vec<Vec<WengLinRating>> = input
+                .iter()
+                .map(|w| w.0.iter().map(|r| (*r).into()).collect())
+                .collect_vec();
             let ranks = input
                 .iter()
                 .map(|w| MultiTeamOutcome::new(w.1 as usize))
@@ -107,7 +171,65 @@ mod openskill {
 
             let new_ratings = weng_lin_multi_team(&teams_and_ranks, &WengLinConfig::default());
 
-            new_ratings.into_iter().map(|r| r[0].into()).collect_vec()
+            new_ratings
+                .into_iter()
+                .map(|t| t.into_iter().map(Into::into).collect())
+                .collect_vec()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::Participant;
+
+    fn two_player_match(rank_1: u8, rank_2: u8) -> Match {
+        Match::new(
+            0,
+            vec![
+                Participant::ranked(1.into(), 0, rank_1),
+                Participant::ranked(2.into(), 1, rank_2),
+            ],
+        )
+    }
+
+    #[test]
+    fn mirror_win_loss_is_rated_as_draw() {
+        let ranker = Ranker::new(RankingConfig::OpenSkill);
+        let (a, b) = (two_player_match(1, 2), two_player_match(2, 1));
+
+        let mut ratings = HashMap::new();
+        ranker.recalc_rating_combined(&mut ratings, &[&a, &b]);
+
+        let mut draw_ratings = HashMap::new();
+        ranker.recalc_rating(&mut draw_ratings, std::iter::once(&two_player_match(1, 1)));
+
+        for id in [1.into(), 2.into()] {
+            assert_eq!(ratings[&id].mu, draw_ratings[&id].mu);
+            assert_eq!(ratings[&id].sigma, draw_ratings[&id].sigma);
         }
     }
+
+    #[test]
+    fn teammates_share_the_outcome() {
+        let participant = |id: i64, team, rank| Participant::ranked(id.into(), team, rank);
+        let m = Match::new(
+            0,
+            vec![
+                participant(1, 0, 1),
+                participant(2, 0, 1),
+                participant(3, 1, 2),
+                participant(4, 1, 2),
+            ],
+        );
+        let ranker = Ranker::new(RankingConfig::OpenSkill);
+        let mut ratings = HashMap::new();
+        ranker.recalc_rating(&mut ratings, std::iter::once(&m));
+
+        let default = ranker.default_rating();
+        assert_eq!(ratings[&1.into()].mu, ratings[&2.into()].mu);
+        assert!(ratings[&1.into()].mu > default.mu);
+        assert!(ratings[&3.into()].mu < default.mu);
+    }
 }
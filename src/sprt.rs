@@ -0,0 +1,201 @@
+use crate::domain::{BotId, Match};
+use std::cmp::Ordering;
+
+/// Sequential probability ratio test deciding whether bot B is stronger than bot A.
+/// H0: elo(B) - elo(A) = elo0, H1: elo(B) - elo(A) = elo1.
+pub struct Sprt {
+    pub bot_a: BotId,
+    pub bot_b: BotId,
+    pub params: SprtParams,
+    /// from the bot B perspective
+    pub wins: usize,
+    pub draws: usize,
+    pub loses: usize,
+    pub status: SprtStatus,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SprtParams {
+    pub elo0: f64,
+    pub elo1: f64,
+    /// probability of accepting H1 when H0 is true
+    pub alpha: f64,
+    /// probability of accepting H0 when H1 is true
+    pub beta: f64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SprtStatus {
+    Running,
+    /// elo difference is closer to elo0, i.e. B is not stronger than A
+    AcceptedH0,
+    /// elo difference is closer to elo1, i.e. B is stronger than A
+    AcceptedH1,
+    /// stopped manually before reaching a decision
+    Stopped,
+}
+
+impl Sprt {
+    pub fn new(bot_a: BotId, bot_b: BotId, params: SprtParams) -> Self {
+        Self {
+            bot_a,
+            bot_b,
+            params,
+            wins: 0,
+            draws: 0,
+            loses: 0,
+            status: SprtStatus::Running,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.status == SprtStatus::Running
+    }
+
+    pub fn involves(&self, id: BotId) -> bool {
+        self.bot_a == id || self.bot_b == id
+    }
+
+    /// Records the match result if it is a head-to-head match of the tested bots.
+    pub fn record(&mut self, m: &Match) {
+        if !self.is_running() || m.participants.len() != 2 {
+            return;
+        }
+        let Some(a) = m.participants.iter().find(|p| p.bot_id == self.bot_a) else {
+            return;
+        };
+        let Some(b) = m.participants.iter().find(|p| p.bot_id == self.bot_b) else {
+            return;
+        };
+
+        match b.rank.cmp(&a.rank) {
+            Ordering::Less => self.wins += 1,
+            Ordering::Equal => self.draws += 1,
+            Ordering::Greater => self.loses += 1,
+        }
+
+        let llr = self.llr();
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            self.status = SprtStatus::AcceptedH1;
+        } else if llr <= lower {
+            self.status = SprtStatus::AcceptedH0;
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if self.is_running() {
+            self.status = SprtStatus::Stopped;
+        }
+    }
+
+    pub fn bounds(&self) -> (f64, f64) {
+        let SprtParams { alpha, beta, .. } = self.params;
+        ((beta / (1.0 - alpha)).ln(), ((1.0 - beta) / alpha).ln())
+    }
+
+    /// Log-likelihood ratio using the normal approximation of the trinomial
+    /// (win/draw/loss) distribution, the same one used by chess engine testing frameworks.
+    pub fn llr(&self) -> f64 {
+        let n = (self.wins + self.draws + self.loses) as f64;
+        if n == 0.0 {
+            return 0.0;
+        }
+        let w = self.wins as f64 / n;
+        let d = self.draws as f64 / n;
+        let score = w + d / 2.0;
+        let variance = w + d / 4.0 - score * score;
+        if variance <= 0.0 {
+            return 0.0;
+        }
+        let s0 = elo_to_score(self.params.elo0);
+        let s1 = elo_to_score(self.params.elo1);
+        n * (s1 - s0) * (2.0 * score - s0 - s1) / (2.0 * variance)
+    }
+
+    /// Estimated elo difference of B over A, `None` until both a win and a loss happened.
+    pub fn elo_diff(&self) -> Option<f64> {
+        let n = (self.wins + self.draws + self.loses) as f64;
+        let score = (self.wins as f64 + self.draws as f64 / 2.0) / n;
+        (score > 0.0 && score < 1.0).then(|| -400.0 * (1.0 / score - 1.0).log10())
+    }
+}
+
+fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domain::Participant;
+
+    fn params() -> SprtParams {
+        SprtParams {
+            elo0: 0.0,
+            elo1: 10.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    fn head_to_head(rank_a: u8, rank_b: u8) -> Match {
+        Match::new(
+            0,
+            vec![
+                Participant::ranked(1.into(), 0, rank_a),
+                Participant::ranked(2.into(), 1, rank_b),
+            ],
+        )
+    }
+
+    #[test]
+    fn llr_is_zero_without_matches() {
+        let sprt = Sprt::new(1.into(), 2.into(), params());
+        assert_eq!(sprt.llr(), 0.0);
+        assert_eq!(sprt.elo_diff(), None);
+    }
+
+    #[test]
+    fn stronger_bot_is_accepted() {
+        let mut sprt = Sprt::new(1.into(), 2.into(), params());
+        for i in 0..10_000 {
+            if !sprt.is_running() {
+                break;
+            }
+            // B wins 60% of the matches
+            let m = if i % 5 < 3 {
+                head_to_head(2, 1)
+            } else {
+                head_to_head(1, 2)
+            };
+            sprt.record(&m);
+        }
+        assert_eq!(sprt.status, SprtStatus::AcceptedH1);
+        assert!(sprt.elo_diff().unwrap() > 10.0);
+    }
+
+    #[test]
+    fn equal_bot_is_rejected() {
+        let mut sprt = Sprt::new(1.into(), 2.into(), params());
+        for i in 0..100_000 {
+            if !sprt.is_running() {
+                break;
+            }
+            let m = match i % 3 {
+                0 => head_to_head(2, 1),
+                1 => head_to_head(1, 2),
+                _ => head_to_head(1, 1),
+            };
+            sprt.record(&m);
+        }
+        assert_eq!(sprt.status, SprtStatus::AcceptedH0);
+    }
+
+    #[test]
+    fn unrelated_matches_are_ignored() {
+        let mut sprt = Sprt::new(1.into(), 3.into(), params());
+        sprt.record(&head_to_head(1, 2));
+        assert_eq!(sprt.wins + sprt.draws + sprt.loses, 0);
+    }
+}
@@ -0,0 +1,211 @@
+use crate::arena::CreateBotResult;
+use crate::client::{local_arena_url, ArenaClient};
+use crate::domain::{BotId, BotName, Language, SourceCode};
+use clap::ValueEnum;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum PreviousVersion {
+    /// Leave previous version as is
+    Keep,
+    /// Archive previous version so it is not matchmade anymore
+    Archive,
+    /// Delete previous version together with its matches
+    Delete,
+}
+
+pub struct WatchOptions {
+    pub file: PathBuf,
+    pub name_prefix: String,
+    pub language: String,
+    pub previous: PreviousVersion,
+    pub interval: Duration,
+    /// default game of the arena if not set
+    pub game: Option<String>,
+    /// API token of a user, required when the arena has users configured
+    pub token: Option<String>,
+}
+
+pub async fn run(arena_path: &Path, url: Option<String>, options: WatchOptions) {
+    let (url, tls_cert) =
+        match url.map_or_else(|| local_arena_url(arena_path), |url| Ok((url, None))) {
+            Ok(res) => res,
+            Err(e) => {
+                eprintln!("Cannot determine arena url: {e:#}");
+                return;
+            }
+        };
+    let language: Language = match options.language.clone().try_into() {
+        Ok(language) => language,
+        Err(e) => {
+            eprintln!("Invalid language: {e}");
+            return;
+        }
+    };
+
+    if let Err(e) = BotName::try_from(bot_name(&options.name_prefix, 0)) {
+        eprintln!("Invalid name prefix: {e}");
+        return;
+    }
+
+    let client = match ArenaClient::new(
+        &url,
+        options.game.as_deref(),
+        options.token.as_deref(),
+        tls_cert.as_deref(),
+    ) {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Cannot create arena client: {e}");
+            return;
+        }
+    };
+    println!(
+        "Watching {} and submitting new versions to {url}, press Ctrl+C to stop",
+        options.file.display()
+    );
+
+    tokio::select! {
+        _ = watch_loop(&client, &options, language) => {},
+        _ = tokio::signal::ctrl_c() => {},
+    }
+}
+
+async fn watch_loop(client: &ArenaClient, options: &WatchOptions, language: Language) {
+    let mut last_hash = None;
+    let mut previous_id: Option<BotId> = None;
+    let mut interval = tokio::time::interval(options.interval);
+
+    loop {
+        interval.tick().await;
+
+        let content = match tokio::fs::read_to_string(&options.file).await {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Cannot read {}: {e}", options.file.display());
+                continue;
+            }
+        };
+        // editors often truncate the file before writing new content
+        if content.trim().is_empty() {
+            continue;
+        }
+
+        let hash = content_hash(&content);
+        if last_hash == Some(hash) {
+            continue;
+        }
+
+        let mut res = submit(client, options, &language, &content, hash).await;
+        // at the author's quota the previous version has to make room for the new one first
+        if matches!(res, Ok(CreateBotResult::QuotaExceeded))
+            && options.previous != PreviousVersion::Keep
+        {
+            if let Some(prev) = previous_id.take() {
+                match handle_previous(client, options.previous, prev).await {
+                    Ok(()) => res = submit(client, options, &language, &content, hash).await,
+                    Err(e) => {
+                        eprintln!("Cannot update previous version: {e:#}");
+                        previous_id = Some(prev);
+                    }
+                }
+            }
+        }
+
+        // last_hash is not updated on errors, so submission is retried on the next tick
+        match res {
+            Ok(CreateBotResult::Created(bot)) => {
+                last_hash = Some(hash);
+                if let Some(prev) = previous_id.replace(bot.id) {
+                    if let Err(e) = handle_previous(client, options.previous, prev).await {
+                        eprintln!("Cannot update previous version: {e:#}");
+                    }
+                }
+            }
+            Ok(CreateBotResult::DuplicateName) => last_hash = Some(hash),
+            Ok(CreateBotResult::QuotaExceeded) => eprintln!(
+                "Cannot submit bot: active bots limit is reached, archive or delete some of your bots"
+            ),
+            Err(e) => eprintln!("Cannot submit bot: {e:#}"),
+        }
+    }
+}
+
+async fn submit(
+    client: &ArenaClient,
+    options: &WatchOptions,
+    language: &Language,
+    content: &str,
+    hash: u64,
+) -> Result<CreateBotResult, anyhow::Error> {
+    let name: BotName = bot_name(&options.name_prefix, hash).try_into()?;
+    let source_code: SourceCode = content.to_string().try_into()?;
+
+    let res = client.create_bot(&name, &source_code, language).await?;
+    match &res {
+        CreateBotResult::Created(bot) => {
+            println!("Submitted {} (id {})", &*bot.name, i64::from(bot.id));
+        }
+        CreateBotResult::DuplicateName => {
+            println!("{} is already submitted, skipping", &*name);
+        }
+        CreateBotResult::QuotaExceeded => {}
+    }
+    Ok(res)
+}
+
+async fn handle_previous(
+    client: &ArenaClient,
+    previous: PreviousVersion,
+    id: BotId,
+) -> Result<(), anyhow::Error> {
+    match previous {
+        PreviousVersion::Keep => {}
+        PreviousVersion::Archive => {
+            let bot = client.archive_bot(id).await?;
+            println!("Archived {}", &*bot.name);
+        }
+        PreviousVersion::Delete => {
+            client.delete_bot(id).await?;
+            println!("Deleted bot {}", i64::from(id));
+        }
+    }
+    Ok(())
+}
+
+/// 64-bit FNV-1a, unlike `DefaultHasher` it is stable across Rust releases,
+/// so the same content keeps the same bot name after a toolchain upgrade
+fn content_hash(content: &str) -> u64 {
+    content.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Bot names are derived from the content hash, so resubmitting the same content
+/// is detected by the arena as a duplicate name.
+fn bot_name(prefix: &str, hash: u64) -> String {
+    format!("{}-{:08x}", prefix, hash as u32)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn same_content_gives_same_bot_name() {
+        let a = bot_name("my", content_hash("fn main() {}"));
+        let b = bot_name("my", content_hash("fn main() {}"));
+        let c = bot_name("my", content_hash("fn main() { }"));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert!(a.starts_with("my-"));
+        assert_eq!(a.len(), "my-".len() + 8);
+    }
+
+    #[test]
+    fn content_hash_is_stable() {
+        assert_eq!(content_hash(""), 0xcbf29ce484222325);
+        assert_eq!(content_hash("a"), 0xaf63dc4c8601ec8c);
+    }
+}